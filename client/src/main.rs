@@ -1,23 +1,32 @@
+mod transport;
+
 use std::{
+    path::PathBuf,
     process::ExitCode,
     sync::{
         Arc,
-        atomic::{AtomicBool, Ordering},
+        atomic::{AtomicU8, Ordering},
     },
+    time::Duration,
 };
 
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
 use clap::Parser;
-use common::consts;
+use common::{config, consts};
 use rustyline::{DefaultEditor, error::ReadlineError};
 use stringzilla::sz;
 use thiserror::Error;
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::TcpStream,
     sync::mpsc,
 };
+use transport::{BoxedReader, BoxedWriter, Transport};
+
+const RECONNECT_HISTORY_FILE: &str = ".chat_client_history";
+const RECONNECT_BASE_DELAY_MS: u64 = 500;
+const RECONNECT_MAX_DELAY_MS: u64 = 30_000;
 
-#[derive(Parser, Debug)]
+#[derive(Parser, Debug, Clone)]
 #[command(author, version, about = "Chat client CLI")]
 struct Args {
     #[arg(long, env = "CHAT_HOST", default_value = "127.0.0.1")]
@@ -28,6 +37,91 @@ struct Args {
 
     #[arg(long, env = "CHAT_USERNAME")]
     username: String,
+
+    /// Password to present via a SASL PLAIN `AUTH` before `JOIN`, for
+    /// deployments that require it. Omit if the server has no credential
+    /// configured for this username.
+    #[arg(long, env = "CHAT_PASSWORD")]
+    password: Option<String>,
+
+    /// Room to join right after `JOIN` succeeds, via `JOINROOM`.
+    #[arg(long, env = "CHAT_ROOM", default_value = "general")]
+    room: String,
+
+    /// Automatically reconnect (with exponential backoff) when the
+    /// connection drops, instead of exiting.
+    #[arg(long, env = "CHAT_RECONNECT")]
+    reconnect: bool,
+
+    /// Give up and exit after this many consecutive failed reconnect
+    /// attempts. Ignored unless `--reconnect` is set.
+    #[arg(long, env = "CHAT_MAX_RECONNECT_ATTEMPTS", default_value_t = 10)]
+    max_reconnect_attempts: u32,
+
+    /// Transport used to reach the server. `quic` negotiates TLS 1.3 and the
+    /// `simple-chat/1` ALPN; the line protocol is identical on top of either.
+    #[arg(long, env = "CHAT_TRANSPORT", value_enum, default_value_t = Transport::Tcp)]
+    transport: Transport,
+
+    /// PEM file of CA certificates trusted to verify the server's TLS
+    /// certificate. Only used for `--transport quic`; the platform's native
+    /// root store is used when omitted.
+    #[arg(long, env = "CHAT_QUIC_CA_CERT")]
+    quic_ca_cert: Option<PathBuf>,
+
+    /// Skip TLS certificate verification for `--transport quic`. Dev only —
+    /// never point this at a server outside your own machine.
+    #[arg(long, env = "CHAT_QUIC_INSECURE")]
+    insecure: bool,
+}
+
+/// Why a joined session ended, so the reconnect supervisor in `main` can
+/// tell a user-requested `leave` apart from a dropped connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SessionEnd {
+    UserLeave,
+    Disconnected,
+}
+
+impl SessionEnd {
+    const fn as_u8(self) -> u8 {
+        match self {
+            Self::UserLeave => 0,
+            Self::Disconnected => 1,
+        }
+    }
+
+    const fn from_u8(v: u8) -> Self {
+        match v {
+            0 => Self::UserLeave,
+            _ => Self::Disconnected,
+        }
+    }
+}
+
+/// Tri-state shutdown signal shared between the reader task, the readline
+/// thread, and the command loop: `None` while the session is still running,
+/// `Some(reason)` once something has asked it to stop.
+#[derive(Debug)]
+struct ShutdownFlag(AtomicU8);
+
+const SHUTDOWN_NONE: u8 = u8::MAX;
+
+impl ShutdownFlag {
+    fn new() -> Arc<Self> {
+        Arc::new(Self(AtomicU8::new(SHUTDOWN_NONE)))
+    }
+
+    fn signal(&self, reason: SessionEnd) {
+        self.0.store(reason.as_u8(), Ordering::SeqCst);
+    }
+
+    fn get(&self) -> Option<SessionEnd> {
+        match self.0.load(Ordering::SeqCst) {
+            SHUTDOWN_NONE => None,
+            v => Some(SessionEnd::from_u8(v)),
+        }
+    }
 }
 
 #[derive(Debug, Error)]
@@ -38,24 +132,37 @@ pub enum ClientError {
     #[error("server error: {0}")]
     ServerError(String),
 
+    #[error("authentication failed: {0}")]
+    AuthFailed(String),
+
     #[error("readline error: {0}")]
     Readline(#[from] ReadlineError),
+
+    #[error("transport error: {0}")]
+    Transport(String),
 }
 
 struct DisconnectedClient {
     host: String,
     port: u16,
     username: String,
+    password: Option<String>,
+    room: String,
+    transport: Transport,
+    quic_ca_cert: Option<PathBuf>,
+    insecure: bool,
 }
 
 struct ConnectedClient {
     username: String,
-    reader: BufReader<tokio::net::tcp::OwnedReadHalf>,
-    writer: tokio::net::tcp::OwnedWriteHalf,
+    password: Option<String>,
+    room: String,
+    reader: BufReader<BoxedReader>,
+    writer: BoxedWriter,
 }
 
 struct JoinedClient {
-    shutdown: Arc<AtomicBool>,
+    shutdown: Arc<ShutdownFlag>,
 }
 
 impl DisconnectedClient {
@@ -64,38 +171,56 @@ impl DisconnectedClient {
             host: args.host,
             port: args.port,
             username: args.username,
+            password: args.password,
+            room: args.room,
+            transport: args.transport,
+            quic_ca_cert: args.quic_ca_cert,
+            insecure: args.insecure,
         }
     }
 
     async fn connect(self) -> Result<ConnectedClient, ClientError> {
         let addr = format!("{}:{}", self.host, self.port);
-        println!("Connecting to {addr}...");
+        println!("Connecting to {addr} over {:?}...", self.transport);
 
-        let stream = TcpStream::connect(&addr).await?;
+        let (reader, writer) = match self.transport {
+            Transport::Tcp => transport::connect_tcp(&addr).await?,
+            Transport::Quic => {
+                transport::connect_quic(&addr, &self.host, self.quic_ca_cert.as_deref(), self.insecure).await?
+            }
+        };
         println!("Connected!");
 
-        let (reader, writer) = stream.into_split();
-        let reader = BufReader::new(reader);
-
         Ok(ConnectedClient {
             username: self.username,
-            reader,
+            password: self.password,
+            room: self.room,
+            reader: BufReader::new(reader),
             writer,
         })
     }
 }
 
 impl ConnectedClient {
-    async fn join(
-        mut self,
-    ) -> Result<
-        (
-            JoinedClient,
-            BufReader<tokio::net::tcp::OwnedReadHalf>,
-            tokio::net::tcp::OwnedWriteHalf,
-        ),
-        ClientError,
-    > {
+    async fn join(mut self) -> Result<(JoinedClient, BufReader<BoxedReader>, BoxedWriter), ClientError> {
+        if let Some(password) = &self.password {
+            let payload = BASE64.encode(format!("{}\0{}\0{}", self.username, self.username, password));
+            let auth_cmd = format!(
+                "{}{} {}\n",
+                consts::CLIENT_AUTH_PREFIX,
+                consts::CLIENT_AUTH_MECHANISM_PLAIN,
+                payload
+            );
+            self.writer.write_all(auth_cmd.as_bytes()).await?;
+            self.writer.flush().await?;
+
+            let mut auth_response = String::new();
+            self.reader.read_line(&mut auth_response).await?;
+            if auth_response.trim().starts_with(consts::SERVER_AUTH_FAILED_PREFIX.trim()) {
+                return Err(ClientError::AuthFailed(auth_response.trim().to_string()));
+            }
+        }
+
         let join_cmd = format!("{}{}\n", consts::CLIENT_JOIN_PREFIX, self.username);
         self.writer.write_all(join_cmd.as_bytes()).await?;
         self.writer.flush().await?;
@@ -107,14 +232,35 @@ impl ConnectedClient {
             return Err(ClientError::ServerError(response.trim().to_string()));
         }
 
+        let join_room_cmd = format!("{}{}\n", consts::CLIENT_JOIN_ROOM_PREFIX, self.room);
+        self.writer.write_all(join_room_cmd.as_bytes()).await?;
+        self.writer.flush().await?;
+
+        // JOINROOM replies with zero or more HISTORY lines, then HISTORY_END,
+        // then the Ok/Error status for the join itself; drain the replay
+        // before checking whether the join succeeded.
+        let mut join_room_response = String::new();
+        loop {
+            join_room_response.clear();
+            self.reader.read_line(&mut join_room_response).await?;
+            let trimmed = join_room_response.trim();
+            if trimmed == consts::SERVER_HISTORY_END_PREFIX || sz::find(trimmed, consts::SERVER_HISTORY_PREFIX) == Some(0) {
+                continue;
+            }
+            break;
+        }
+        if join_room_response.trim().starts_with(consts::SERVER_ERR_PREFIX.trim()) {
+            return Err(ClientError::ServerError(join_room_response.trim().to_string()));
+        }
+
         println!(
-            "Joined as '{}'. Type 'send <message>' or 'leave' to exit.",
-            self.username
+            "Joined as '{}' in room '{}'. Type 'send <message>' or 'leave' to exit.",
+            self.username, self.room
         );
         println!("Use arrow keys for history navigation.\n");
 
         let joined = JoinedClient {
-            shutdown: Arc::new(AtomicBool::new(false)),
+            shutdown: ShutdownFlag::new(),
         };
 
         Ok((joined, self.reader, self.writer))
@@ -122,11 +268,7 @@ impl ConnectedClient {
 }
 
 impl JoinedClient {
-    async fn run(
-        self,
-        reader: BufReader<tokio::net::tcp::OwnedReadHalf>,
-        mut writer: tokio::net::tcp::OwnedWriteHalf,
-    ) -> Result<(), ClientError> {
+    async fn run(self, reader: BufReader<BoxedReader>, mut writer: BoxedWriter) -> Result<SessionEnd, ClientError> {
         let (cmd_tx, mut cmd_rx) = mpsc::channel::<String>(32);
 
         let shutdown_clone = Arc::clone(&self.shutdown);
@@ -140,13 +282,14 @@ impl JoinedClient {
         });
 
         while let Some(input) = cmd_rx.recv().await {
-            if self.shutdown.load(Ordering::SeqCst) {
+            if self.shutdown.get().is_some() {
                 break;
             }
 
             let trimmed = input.trim();
 
             if trimmed.eq_ignore_ascii_case(consts::CLIENT_LEAVE_CMD) {
+                self.shutdown.signal(SessionEnd::UserLeave);
                 writer
                     .write_all(format!("{}\n", consts::CLIENT_LEAVE_PREFIX).as_bytes())
                     .await?;
@@ -163,46 +306,100 @@ impl JoinedClient {
                     break;
                 }
                 let _ = writer.flush().await;
+            } else if trimmed.eq_ignore_ascii_case(consts::CLIENT_HISTORY_CMD)
+                || trimmed.to_uppercase().starts_with(consts::CLIENT_HISTORY_CMD)
+            {
+                let cmd = format!("{trimmed}\n");
+                if let Err(e) = writer.write_all(cmd.as_bytes()).await {
+                    eprintln!("Failed to send: {e}");
+                    break;
+                }
+                let _ = writer.flush().await;
+            } else if let Some(rest) = trimmed
+                .strip_prefix("msg ")
+                .or_else(|| trimmed.strip_prefix(consts::CLIENT_MSG_PREFIX))
+            {
+                let cmd = format!("{}{}\n", consts::CLIENT_MSG_PREFIX, rest);
+                if let Err(e) = writer.write_all(cmd.as_bytes()).await {
+                    eprintln!("Failed to send: {e}");
+                    break;
+                }
+                let _ = writer.flush().await;
+            } else if trimmed.eq_ignore_ascii_case(consts::CLIENT_WHO_CMD) {
+                let cmd = format!("{}\n", consts::CLIENT_WHO_PREFIX);
+                if let Err(e) = writer.write_all(cmd.as_bytes()).await {
+                    eprintln!("Failed to send: {e}");
+                    break;
+                }
+                let _ = writer.flush().await;
+            } else if let Some(room) = trimmed
+                .strip_prefix("join ")
+                .or_else(|| trimmed.strip_prefix(consts::CLIENT_JOIN_ROOM_PREFIX))
+            {
+                let cmd = format!("{}{room}\n", consts::CLIENT_JOIN_ROOM_PREFIX);
+                if let Err(e) = writer.write_all(cmd.as_bytes()).await {
+                    eprintln!("Failed to send: {e}");
+                    break;
+                }
+                let _ = writer.flush().await;
+            } else if let Some(room) = trimmed
+                .strip_prefix("part ")
+                .or_else(|| trimmed.strip_prefix(consts::CLIENT_PART_ROOM_PREFIX))
+            {
+                let cmd = format!("{}{room}\n", consts::CLIENT_PART_ROOM_PREFIX);
+                if let Err(e) = writer.write_all(cmd.as_bytes()).await {
+                    eprintln!("Failed to send: {e}");
+                    break;
+                }
+                let _ = writer.flush().await;
             } else {
-                println!("Unknown command. Use 'send <message>' or 'leave'.");
+                println!(
+                    "Unknown command. Use 'send <message>', 'msg <user> <message>', 'join <room>', \
+                     'part <room>', 'history [limit]', 'who' or 'leave'."
+                );
             }
         }
 
-        self.shutdown.store(true, Ordering::SeqCst);
+        // `cmd_rx.recv()` can also return `None` if both senders dropped
+        // without anyone signaling shutdown (e.g. the readline thread died);
+        // treat that as a dropped connection rather than a user-requested one.
+        let reason = self.shutdown.get().unwrap_or(SessionEnd::Disconnected);
+        self.shutdown.signal(reason);
         let _ = reader_handle.await;
         let _ = readline_handle.join();
 
-        Ok(())
+        Ok(reason)
     }
 }
 
-async fn read_server_messages(mut reader: BufReader<tokio::net::tcp::OwnedReadHalf>, shutdown: Arc<AtomicBool>) {
+async fn read_server_messages(mut reader: BufReader<BoxedReader>, shutdown: Arc<ShutdownFlag>) {
     let mut line = String::new();
     loop {
         line.clear();
         match reader.read_line(&mut line).await {
             Ok(0) => {
                 println!("\nDisconnected from server.");
-                shutdown.store(true, Ordering::SeqCst);
+                shutdown.signal(SessionEnd::Disconnected);
                 break;
             }
             Ok(_) => handle_server_message(&line),
             Err(e) => {
                 eprintln!("\nRead error: {e}");
-                shutdown.store(true, Ordering::SeqCst);
+                shutdown.signal(SessionEnd::Disconnected);
                 break;
             }
         }
     }
 }
 
-fn read_user_input(cmd_tx: &mpsc::Sender<String>, shutdown: &Arc<AtomicBool>) {
+fn read_user_input(cmd_tx: &mpsc::Sender<String>, shutdown: &Arc<ShutdownFlag>) {
     let Ok(mut rl) = DefaultEditor::new() else {
         return;
     };
+    let _ = rl.load_history(RECONNECT_HISTORY_FILE);
 
     loop {
-        if shutdown.load(Ordering::SeqCst) {
+        if shutdown.get().is_some() {
             break;
         }
 
@@ -229,16 +426,38 @@ fn read_user_input(cmd_tx: &mpsc::Sender<String>, shutdown: &Arc<AtomicBool>) {
             Err(_) => break,
         }
     }
+
+    let _ = rl.save_history(RECONNECT_HISTORY_FILE);
+}
+
+/// Parses a `sender\x1ftimestamp_rfc3339\x1fcontent` chat-message frame
+/// (see `ChatMessage::serialize` on the server) into its three fields.
+fn parse_chat_frame(payload: &str) -> Option<(&str, &str, &str)> {
+    let mut fields = payload.splitn(3, '\u{1f}');
+    Some((fields.next()?, fields.next()?, fields.next()?))
+}
+
+/// Renders an RFC3339 timestamp as `HH:MM:SS` in the configured server
+/// timezone, falling back to a placeholder if parsing fails either step.
+fn format_ts(ts_str: &str) -> String {
+    let Ok(ts) = ts_str.parse::<jiff::Timestamp>() else {
+        return "??:??:??".to_string();
+    };
+    let tz_name = config::get_server_tz().unwrap_or_else(|_| "UTC".to_string());
+    let tz = jiff::tz::TimeZone::get(&tz_name).unwrap_or(jiff::tz::TimeZone::UTC);
+    ts.to_zoned(tz).strftime("%H:%M:%S").to_string()
 }
 
 fn handle_server_message(line: &str) {
     let trimmed = line.trim();
     if sz::find(trimmed, consts::SERVER_BROADCAST_PREFIX) == Some(0) {
         let rest = trimmed.get(consts::SERVER_BROADCAST_PREFIX.len()..).unwrap_or("");
-        if let Some(idx) = sz::find(rest, ":") {
-            let from = rest.get(..idx).unwrap_or("?");
-            let text = rest.get(idx.saturating_add(1)..).unwrap_or("");
-            println!("\r[{from}]: {text}");
+        if let Some((room, payload)) = rest.split_once(' ') {
+            if let Some((from, ts, text)) = parse_chat_frame(payload) {
+                println!("\r[{room}] [{}] [{from}]: {text}", format_ts(ts));
+            } else {
+                println!("\r[{room}] {payload}");
+            }
         } else {
             println!("\r{trimmed}");
         }
@@ -248,37 +467,182 @@ fn handle_server_message(line: &str) {
     } else if sz::find(trimmed, consts::SERVER_LEFT_PREFIX) == Some(0) {
         let user = trimmed.get(consts::SERVER_LEFT_PREFIX.len()..).unwrap_or("?");
         println!("\r*** {user} left the chat ***");
+    } else if sz::find(trimmed, consts::SERVER_DM_PREFIX) == Some(0) {
+        let rest = trimmed.get(consts::SERVER_DM_PREFIX.len()..).unwrap_or("");
+        if let Some((from, ts, text)) = parse_chat_frame(rest) {
+            println!("\r[{}] [DM from {from}]: {text}", format_ts(ts));
+        } else {
+            println!("\r{trimmed}");
+        }
+    } else if sz::find(trimmed, consts::SERVER_USERS_PREFIX) == Some(0) {
+        let names = trimmed.get(consts::SERVER_USERS_PREFIX.len()..).unwrap_or("");
+        println!("\r*** online: {names} ***");
+    } else if sz::find(trimmed, consts::SERVER_SHUTDOWN_PREFIX) == Some(0) {
+        let reason = trimmed.get(consts::SERVER_SHUTDOWN_PREFIX.len()..).unwrap_or("");
+        println!("\r*** server shutting down: {reason} ***");
+    } else if sz::find(trimmed, consts::SERVER_AUTH_FAILED_PREFIX) == Some(0) {
+        let reason = trimmed.get(consts::SERVER_AUTH_FAILED_PREFIX.len()..).unwrap_or("");
+        println!("\rAuthentication failed: {reason}");
+    } else if sz::find(trimmed, consts::SERVER_HISTORY_PREFIX) == Some(0) {
+        let rest = trimmed.get(consts::SERVER_HISTORY_PREFIX.len()..).unwrap_or("");
+        let mut fields = rest.splitn(3, ' ');
+        let sender = fields.nth(1).unwrap_or("?");
+        let body = fields.next().unwrap_or("");
+        println!("\r[history] [{sender}]: {body}");
+    } else if trimmed == consts::SERVER_HISTORY_END_PREFIX {
+        println!("\r--- end of history ---");
     } else if trimmed != "OK" {
         println!("\r{trimmed}");
     }
 }
 
+/// Derives a small pseudo-random jitter (0..max_ms) from the current time,
+/// so a fleet of clients reconnecting after the same outage doesn't retry in
+/// lockstep. Not cryptographic; good enough for spreading out retries, and
+/// avoids pulling in a `rand` dependency for a single call site.
+fn jitter_ms(max_ms: u64) -> u64 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| u64::from(d.subsec_nanos()) % max_ms.max(1))
+        .unwrap_or(0)
+}
+
+/// Sleeps for an exponentially-growing delay ahead of reconnect attempt
+/// `attempt` (1-indexed): doubles from `RECONNECT_BASE_DELAY_MS` each
+/// attempt, capped at `RECONNECT_MAX_DELAY_MS`, plus up to 20% jitter.
+async fn reconnect_backoff(attempt: u32) {
+    let shift = attempt.saturating_sub(1).min(6);
+    let base = RECONNECT_BASE_DELAY_MS.saturating_mul(1u64 << shift);
+    let capped = base.min(RECONNECT_MAX_DELAY_MS);
+    let delay = capped + jitter_ms(capped / 5 + 1);
+    tokio::time::sleep(Duration::from_millis(delay)).await;
+}
+
 #[tokio::main]
 async fn main() -> ExitCode {
     let args = Args::parse();
+    let reconnect = args.reconnect;
+    let max_attempts = args.max_reconnect_attempts;
 
-    let disconnected = DisconnectedClient::new(args);
+    let mut attempt: u32 = 0;
+    loop {
+        let disconnected = DisconnectedClient::new(args.clone());
 
-    let connected = match disconnected.connect().await {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Connection error: {e}");
-            return ExitCode::FAILURE;
+        let connected = match disconnected.connect().await {
+            Ok(c) => c,
+            Err(e) => {
+                if reconnect && attempt < max_attempts {
+                    attempt += 1;
+                    println!("Reconnecting (attempt {attempt})...");
+                    reconnect_backoff(attempt).await;
+                    continue;
+                }
+                eprintln!("Connection error: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let (joined, reader, writer) = match connected.join().await {
+            Ok(j) => j,
+            Err(e) => {
+                if reconnect && attempt < max_attempts {
+                    attempt += 1;
+                    println!("Reconnecting (attempt {attempt})...");
+                    reconnect_backoff(attempt).await;
+                    continue;
+                }
+                eprintln!("Join error: {e}");
+                return ExitCode::FAILURE;
+            }
+        };
+
+        let result = joined.run(reader, writer).await;
+        // A session that ran at all, even briefly, means the connection was
+        // good; reset the backoff so a later drop starts from the base delay.
+        attempt = 0;
+
+        match result {
+            Ok(SessionEnd::UserLeave) => return ExitCode::SUCCESS,
+            Ok(SessionEnd::Disconnected) => {
+                if !reconnect {
+                    return ExitCode::SUCCESS;
+                }
+            }
+            Err(e) => {
+                eprintln!("Error: {e}");
+                if !reconnect {
+                    return ExitCode::FAILURE;
+                }
+            }
         }
-    };
 
-    let (joined, reader, writer) = match connected.join().await {
-        Ok(j) => j,
-        Err(e) => {
-            eprintln!("Join error: {e}");
+        if attempt >= max_attempts {
+            eprintln!("Giving up after {max_attempts} reconnect attempts.");
             return ExitCode::FAILURE;
         }
-    };
+        attempt += 1;
+        println!("Reconnecting (attempt {attempt})...");
+        reconnect_backoff(attempt).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_chat_frame_real_broadcast_payload() {
+        // Shaped like the payload a server `BROADCAST <room> <payload>` line
+        // actually carries: `ChatMessage::serialize()`'s
+        // `sender\x1fts\x1fcontent` frame, not a colon-joined one.
+        let payload = "charlie\u{1f}2024-01-01T00:00:00Z\u{1f}Hello everyone!";
+
+        let (from, ts, text) = parse_chat_frame(payload).expect("should parse a well-formed frame");
+        assert_eq!(from, "charlie");
+        assert_eq!(ts, "2024-01-01T00:00:00Z");
+        assert_eq!(text, "Hello everyone!");
+    }
+
+    #[test]
+    fn test_parse_chat_frame_content_containing_separator_stays_in_last_field() {
+        // splitn(3, ..) means an embedded U+001F in the message content
+        // (however it got there) lands in the third field rather than
+        // truncating it.
+        let payload = "dave\u{1f}2024-01-01T00:00:00Z\u{1f}part1\u{1f}part2";
+
+        let (from, ts, text) = parse_chat_frame(payload).expect("should parse");
+        assert_eq!(from, "dave");
+        assert_eq!(ts, "2024-01-01T00:00:00Z");
+        assert_eq!(text, "part1\u{1f}part2");
+    }
 
-    if let Err(e) = joined.run(reader, writer).await {
-        eprintln!("Error: {e}");
-        return ExitCode::FAILURE;
+    #[test]
+    fn test_parse_chat_frame_missing_fields_returns_none() {
+        assert!(parse_chat_frame("onlyonefield").is_none());
+        assert!(parse_chat_frame("sender\u{1f}ts_only").is_none());
     }
 
-    ExitCode::SUCCESS
+    #[test]
+    fn test_handle_server_message_broadcast_matches_real_wire_format() {
+        // The exact line shape `connection::handle_joined_session` sends
+        // for `Ok(ClientCommand::Send { .. })`:
+        // `ServerMessage::BroadcastMessage { room, text }.to_string()`
+        // where `text` is a real `ChatMessage::serialize()` frame.
+        let line = format!(
+            "{}general charlie\u{1f}2024-01-01T00:00:00Z\u{1f}Hello everyone!",
+            consts::SERVER_BROADCAST_PREFIX
+        );
+
+        let rest = line
+            .strip_prefix(consts::SERVER_BROADCAST_PREFIX)
+            .expect("line should start with the BROADCAST prefix");
+        let (room, payload) = rest.split_once(' ').expect("room and payload should be space-separated");
+        assert_eq!(room, "general");
+
+        let (from, ts, text) = parse_chat_frame(payload).expect("payload should parse as a chat frame");
+        assert_eq!(from, "charlie");
+        assert_eq!(text, "Hello everyone!");
+        assert!(ts.parse::<jiff::Timestamp>().is_ok());
+    }
 }
@@ -0,0 +1,150 @@
+//! Transport backends for the client's connection to the server.
+//!
+//! Both transports carry the exact same newline-delimited line protocol
+//! (the `consts::*` prefixes); only how bytes reach the server differs.
+//! `DisconnectedClient::connect` picks one based on `--transport` and hands
+//! back boxed `AsyncRead`/`AsyncWrite` halves so the rest of the client
+//! (`JoinedClient::run`, `read_server_messages`) doesn't need to know which
+//! one is in use.
+
+use std::{path::Path, sync::Arc};
+
+use tokio::io::{AsyncRead, AsyncWrite};
+
+use crate::ClientError;
+
+/// ALPN protocol identifier negotiated over QUIC. Fixed, since this crate
+/// only ever speaks one application protocol over the encrypted stream.
+pub const QUIC_ALPN: &[u8] = b"simple-chat/1";
+
+/// Which transport to use to reach the server. The line protocol on top is
+/// identical either way; this only changes how bytes get there.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transport {
+    /// Plain, unencrypted TCP (the original behavior).
+    Tcp,
+    /// QUIC over TLS 1.3, negotiating the [`QUIC_ALPN`] protocol.
+    Quic,
+}
+
+/// Either transport's read half, boxed so callers don't need to be generic
+/// over the concrete stream type.
+pub type BoxedReader = Box<dyn AsyncRead + Unpin + Send>;
+/// Either transport's write half, boxed for the same reason.
+pub type BoxedWriter = Box<dyn AsyncWrite + Unpin + Send>;
+
+/// Connects to `addr` over plain TCP and splits it into boxed halves.
+pub async fn connect_tcp(addr: &str) -> Result<(BoxedReader, BoxedWriter), ClientError> {
+    let stream = tokio::net::TcpStream::connect(addr).await?;
+    let (reader, writer) = stream.into_split();
+    Ok((Box::new(reader), Box::new(writer)))
+}
+
+/// Opens a QUIC connection to `addr` (SNI'd as `server_name`), negotiating
+/// [`QUIC_ALPN`], and opens a single bidirectional stream for the session.
+///
+/// `ca_cert` points at a PEM file of trusted CA certificates; when absent,
+/// the platform's native root store is used. `insecure` skips certificate
+/// verification entirely and exists for local development only — it must
+/// never be set against a server outside the developer's own machine.
+pub async fn connect_quic(
+    addr: &str,
+    server_name: &str,
+    ca_cert: Option<&Path>,
+    insecure: bool,
+) -> Result<(BoxedReader, BoxedWriter), ClientError> {
+    let socket_addr = tokio::net::lookup_host(addr)
+        .await?
+        .next()
+        .ok_or_else(|| ClientError::Transport(format!("could not resolve '{addr}'")))?;
+
+    let client_config = build_client_config(ca_cert, insecure)?;
+
+    let mut endpoint = quinn::Endpoint::client("0.0.0.0:0".parse().expect("valid unspecified client bind address"))?;
+    endpoint.set_default_client_config(client_config);
+
+    let connection = endpoint
+        .connect(socket_addr, server_name)
+        .map_err(|e| ClientError::Transport(e.to_string()))?
+        .await
+        .map_err(|e| ClientError::Transport(e.to_string()))?;
+
+    let (send, recv) = connection
+        .open_bi()
+        .await
+        .map_err(|e| ClientError::Transport(e.to_string()))?;
+
+    Ok((Box::new(recv), Box::new(send)))
+}
+
+fn build_client_config(ca_cert: Option<&Path>, insecure: bool) -> Result<quinn::ClientConfig, ClientError> {
+    let mut roots = rustls::RootCertStore::empty();
+    if let Some(path) = ca_cert {
+        let pem = std::fs::read(path)?;
+        for cert in rustls_pemfile::certs(&mut pem.as_slice()).flatten() {
+            let _ = roots.add(cert);
+        }
+    } else {
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    }
+
+    let mut tls_config = rustls::ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+
+    if insecure {
+        tls_config
+            .dangerous()
+            .set_certificate_verifier(Arc::new(InsecureServerCertVerifier));
+    }
+
+    tls_config.alpn_protocols = vec![QUIC_ALPN.to_vec()];
+
+    Ok(quinn::ClientConfig::new(Arc::new(
+        quinn::crypto::rustls::QuicClientConfig::try_from(tls_config)
+            .map_err(|e| ClientError::Transport(e.to_string()))?,
+    )))
+}
+
+/// Accepts any server certificate without verification. Only ever
+/// constructed when the operator passes `--insecure`, for pointing the
+/// client at a local dev server with a self-signed certificate.
+#[derive(Debug)]
+struct InsecureServerCertVerifier;
+
+impl rustls::client::danger::ServerCertVerifier for InsecureServerCertVerifier {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &rustls::pki_types::CertificateDer<'_>,
+        _intermediates: &[rustls::pki_types::CertificateDer<'_>],
+        _server_name: &rustls::pki_types::ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: rustls::pki_types::UnixTime,
+    ) -> Result<rustls::client::danger::ServerCertVerified, rustls::Error> {
+        Ok(rustls::client::danger::ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        _message: &[u8],
+        _cert: &rustls::pki_types::CertificateDer<'_>,
+        _dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        _message: &[u8],
+        _cert: &rustls::pki_types::CertificateDer<'_>,
+        _dss: &rustls::DigitallySignedStruct,
+    ) -> Result<rustls::client::danger::HandshakeSignatureValid, rustls::Error> {
+        Ok(rustls::client::danger::HandshakeSignatureValid::assertion())
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<rustls::SignatureScheme> {
+        rustls::crypto::ring::default_provider()
+            .signature_verification_algorithms
+            .supported_schemes()
+    }
+}
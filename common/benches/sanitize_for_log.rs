@@ -0,0 +1,20 @@
+//! Benchmarks for [`common::security::sanitize_for_log`], comparing clean
+//! ASCII input (the common case this chunk's lookup-table/SIMD rewrite
+//! targets) against input dense with control characters, which forces the
+//! scalar escape path on every chunk.
+
+use common::security::sanitize_for_log;
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+
+fn bench_sanitize_for_log(c: &mut Criterion) {
+    let clean = "a".repeat(4096);
+    let dirty = "a\nb\rc\td\0".repeat(512);
+
+    let mut group = c.benchmark_group("sanitize_for_log");
+    group.bench_function("clean_ascii_4k", |b| b.iter(|| sanitize_for_log(black_box(&clean))));
+    group.bench_function("control_heavy_4k", |b| b.iter(|| sanitize_for_log(black_box(&dirty))));
+    group.finish();
+}
+
+criterion_group!(benches, bench_sanitize_for_log);
+criterion_main!(benches);
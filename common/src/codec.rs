@@ -0,0 +1,201 @@
+//! Length-prefixed binary framing for [`tcp_message`](crate::tcp_message)
+//! wire types, for use as a `tokio_util::codec::Framed` codec over a raw
+//! byte stream.
+//!
+//! The line-based `trim()`/newline framing `tcp_message`'s `decode()`
+//! assumes doesn't hold once reads stop aligning to message boundaries (a
+//! TCP stream has no notion of "one read = one message"), and trimming
+//! corrupts any payload whose meaningful bytes happen to look like
+//! whitespace. This codec instead prefixes each frame with a big-endian
+//! `u32` byte length, so the inner `WireDecode::decode` only ever sees an
+//! exact, already-delimited slice.
+
+use std::{io, marker::PhantomData};
+
+use bytes::{Buf, BufMut, BytesMut};
+use thiserror::Error;
+use tokio_util::codec::{Decoder, Encoder};
+
+use crate::tcp_message::{WireDecode, WireEncode};
+
+/// Size of the big-endian length prefix written before each frame's payload.
+const LENGTH_PREFIX_BYTES: usize = 4;
+
+/// Largest frame payload this codec will buffer for, in bytes. A prefix
+/// claiming more than this is treated as a protocol violation rather than an
+/// allocation request, so a corrupt or hostile peer can't make us buffer an
+/// unbounded amount of memory waiting for bytes that may never arrive.
+pub const MAX_FRAME_LEN: u32 = 1 << 20;
+
+/// Error returned by [`MessageCodec`] on encode/decode failure.
+#[derive(Debug, Error)]
+pub enum CodecError<E> {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("frame length {0} exceeds MAX_FRAME_LEN ({MAX_FRAME_LEN})")]
+    FrameTooLarge(u32),
+    #[error("failed to decode frame payload: {0}")]
+    Decode(E),
+}
+
+/// `tokio_util::codec::{Encoder, Decoder}` implementation shared by
+/// [`ClientMessage`](crate::tcp_message::ClientMessage) and
+/// [`ServerMessage`](crate::tcp_message::ServerMessage): writes/reads a
+/// `u32` big-endian length prefix around the [`WireEncode`]/[`WireDecode`]
+/// payload, so frames don't need to be newline- or whitespace-delimited.
+#[derive(Debug, Default)]
+pub struct MessageCodec<T> {
+    /// Length of the frame currently being assembled, once the prefix has
+    /// been read, so a decode call spanning multiple `poll_read`s doesn't
+    /// need to re-parse the prefix on every attempt.
+    pending_len: Option<u32>,
+    _marker: PhantomData<T>,
+}
+
+impl<T> MessageCodec<T> {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            pending_len: None,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T: WireEncode + WireDecode> Encoder<T> for MessageCodec<T> {
+    type Error = CodecError<T::Error>;
+
+    fn encode(&mut self, item: T, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let payload = item.encode();
+        let len = u32::try_from(payload.len()).map_err(|_| CodecError::FrameTooLarge(u32::MAX))?;
+        if len > MAX_FRAME_LEN {
+            return Err(CodecError::FrameTooLarge(len));
+        }
+
+        dst.reserve(LENGTH_PREFIX_BYTES + payload.len());
+        dst.put_u32(len);
+        dst.extend_from_slice(&payload);
+        Ok(())
+    }
+}
+
+impl<T: WireDecode> Decoder for MessageCodec<T> {
+    type Item = T;
+    type Error = CodecError<T::Error>;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Self::Item>, Self::Error> {
+        let len = match self.pending_len {
+            Some(len) => len,
+            None => {
+                if src.len() < LENGTH_PREFIX_BYTES {
+                    return Ok(None);
+                }
+                let len = u32::from_be_bytes(src[..LENGTH_PREFIX_BYTES].try_into().unwrap_or_default());
+                if len > MAX_FRAME_LEN {
+                    return Err(CodecError::FrameTooLarge(len));
+                }
+                src.advance(LENGTH_PREFIX_BYTES);
+                self.pending_len = Some(len);
+                len
+            }
+        };
+
+        let len = len as usize;
+        if src.len() < len {
+            src.reserve(len.saturating_sub(src.len()));
+            return Ok(None);
+        }
+
+        let frame = src.split_to(len);
+        self.pending_len = None;
+        T::decode(&frame).map(Some).map_err(CodecError::Decode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+
+    use super::*;
+    use crate::tcp_message::ClientMessage;
+
+    #[test]
+    fn test_encode_writes_length_prefix() {
+        let mut codec = MessageCodec::<ClientMessage>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(ClientMessage::Join { username: "alice".to_string() }, &mut buf)
+            .expect("should encode");
+
+        assert_eq!(&buf[..4], &10u32.to_be_bytes());
+        assert_eq!(&buf[4..], b"JOIN|alice");
+    }
+
+    #[test]
+    fn test_decode_returns_none_until_full_frame_buffered() {
+        let mut codec = MessageCodec::<ClientMessage>::new();
+        let mut buf = BytesMut::new();
+        codec
+            .encode(ClientMessage::Join { username: "alice".to_string() }, &mut buf)
+            .expect("should encode");
+
+        let mut partial = BytesMut::from(&buf[..buf.len() - 1]);
+        assert!(codec.decode(&mut partial).expect("should not error").is_none());
+    }
+
+    #[test]
+    fn test_roundtrip_encode_decode() {
+        let mut codec = MessageCodec::<ClientMessage>::new();
+        let mut buf = BytesMut::new();
+        let original = ClientMessage::Send {
+            message: "contains|pipe\\and\nnewline".to_string(),
+        };
+        codec.encode(original.clone(), &mut buf).expect("should encode");
+
+        let decoded = codec.decode(&mut buf).expect("should decode").expect("frame complete");
+        assert_eq!(decoded, original);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn test_decode_handles_split_across_two_buffers() {
+        let mut codec = MessageCodec::<ClientMessage>::new();
+        let mut full = BytesMut::new();
+        let original = ClientMessage::Leave;
+        codec.encode(original.clone(), &mut full).expect("should encode");
+
+        let split_at = full.len() / 2;
+        let mut buf = BytesMut::from(&full[..split_at]);
+        assert!(codec.decode(&mut buf).expect("should not error").is_none());
+
+        buf.extend_from_slice(&full[split_at..]);
+        let decoded = codec.decode(&mut buf).expect("should decode").expect("frame complete");
+        assert_eq!(decoded, original);
+    }
+
+    #[test]
+    fn test_decode_rejects_oversized_frame_prefix() {
+        let mut codec = MessageCodec::<ClientMessage>::new();
+        let mut buf = BytesMut::new();
+        buf.put_u32(MAX_FRAME_LEN + 1);
+
+        let result = codec.decode(&mut buf);
+        assert!(matches!(result, Err(CodecError::FrameTooLarge(_))));
+    }
+
+    #[test]
+    fn test_decode_multiple_frames_in_one_buffer() {
+        let mut codec = MessageCodec::<ClientMessage>::new();
+        let mut buf = BytesMut::new();
+        codec.encode(ClientMessage::Leave, &mut buf).expect("should encode");
+        codec
+            .encode(ClientMessage::Join { username: "bob".to_string() }, &mut buf)
+            .expect("should encode");
+
+        let first = codec.decode(&mut buf).expect("should decode").expect("frame complete");
+        assert_eq!(first, ClientMessage::Leave);
+
+        let second = codec.decode(&mut buf).expect("should decode").expect("frame complete");
+        assert_eq!(second, ClientMessage::Join { username: "bob".to_string() });
+    }
+}
@@ -6,6 +6,14 @@ pub const APP_ENV_DEFAULT_VALUE: &str = "development";
 pub const APP_ENV_PROD_VALUE: &str = "production";
 pub const DEFAULT_LOG_LEVEL: &str = "CHAT_APP_LOG_LEVEL";
 pub const DEFAULT_LOG_LEVEL_DEFAULT_VALUE: &str = "info";
+pub const AUTH_CREDENTIALS_PATH_ENV: &str = "CHAT_AUTH_CREDENTIALS_PATH";
+pub const ROOM_HISTORY_CAPACITY_ENV: &str = "CHAT_ROOM_HISTORY_CAPACITY";
+pub const ROOM_HISTORY_CAPACITY_DEFAULT_VALUE: usize = 256;
+pub const OTLP_ENDPOINT_ENV: &str = "CHAT_APP_OTLP_ENDPOINT";
+pub const QUIC_PORT_ENV: &str = "CHAT_QUIC_PORT";
+pub const QUIC_PORT_DEFAULT_VALUE: u16 = 4433;
+pub const QUIC_CERT_PATH_ENV: &str = "CHAT_QUIC_CERT_PATH";
+pub const QUIC_KEY_PATH_ENV: &str = "CHAT_QUIC_KEY_PATH";
 
 /// Returns the server timezone from the `TZ` environment variable.
 ///
@@ -61,3 +69,44 @@ pub fn log_level() -> Result<String, InvalidLogLevelError> {
 pub fn is_production() -> bool {
     app_env() == APP_ENV_PROD_VALUE
 }
+
+/// Returns the configured per-room history retention capacity, falling back
+/// to [`ROOM_HISTORY_CAPACITY_DEFAULT_VALUE`] when unset or not a positive
+/// integer.
+#[must_use]
+pub fn room_history_capacity() -> usize {
+    env::var(ROOM_HISTORY_CAPACITY_ENV)
+        .ok()
+        .and_then(|v| v.parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(ROOM_HISTORY_CAPACITY_DEFAULT_VALUE)
+}
+
+/// Returns the configured OTLP collector endpoint, if any.
+///
+/// When unset, the telemetry layer skips OTLP export entirely and only logs
+/// to stdout.
+#[must_use]
+pub fn otlp_endpoint() -> Option<String> {
+    env::var(OTLP_ENDPOINT_ENV).ok()
+}
+
+/// Returns the configured QUIC listener port, falling back to
+/// [`QUIC_PORT_DEFAULT_VALUE`] when unset or not a valid port number.
+#[must_use]
+pub fn quic_port() -> u16 {
+    env::var(QUIC_PORT_ENV)
+        .ok()
+        .and_then(|v| v.parse::<u16>().ok())
+        .unwrap_or(QUIC_PORT_DEFAULT_VALUE)
+}
+
+/// Returns the configured QUIC TLS certificate and private key PEM paths,
+/// if both are set. The QUIC listener only starts when this returns `Some`;
+/// it's an optional transport alongside the always-on TCP listener.
+#[must_use]
+pub fn quic_cert_paths() -> Option<(String, String)> {
+    let cert = env::var(QUIC_CERT_PATH_ENV).ok()?;
+    let key = env::var(QUIC_KEY_PATH_ENV).ok()?;
+    Some((cert, key))
+}
@@ -15,3 +15,51 @@ pub const CLIENT_SEND_PREFIX: &str = "SEND "; // SPACE
 pub const CLIENT_SEND_CMD: &str = "SEND";
 pub const CLIENT_LEAVE_CMD: &str = "LEAVE";
 pub const CLIENT_LEAVE_PREFIX: &str = "LEAVE";
+
+pub const CLIENT_JOIN_ROOM_CMD: &str = "JOINROOM";
+pub const CLIENT_JOIN_ROOM_PREFIX: &str = "JOINROOM "; // SPACE
+pub const CLIENT_PART_ROOM_CMD: &str = "PART";
+pub const CLIENT_PART_ROOM_PREFIX: &str = "PART "; // SPACE
+
+pub const CLIENT_HISTORY_CMD: &str = "HISTORY";
+pub const CLIENT_HISTORY_PREFIX: &str = "HISTORY"; // optional trailing " <limit>" | " LATEST <n>" | " AFTER <rfc3339>"
+pub const CLIENT_HISTORY_LATEST_KEYWORD: &str = "LATEST";
+pub const CLIENT_HISTORY_AFTER_KEYWORD: &str = "AFTER";
+
+pub const SERVER_HISTORY_CMD: &str = "HISTORY";
+pub const SERVER_HISTORY_PREFIX: &str = "HISTORY "; // SPACE
+pub const SERVER_HISTORY_END_CMD: &str = "HISTORY_END";
+pub const SERVER_HISTORY_END_PREFIX: &str = "HISTORY_END";
+
+pub const CLIENT_AUTH_CMD: &str = "AUTH";
+pub const CLIENT_AUTH_PREFIX: &str = "AUTH "; // SPACE
+pub const CLIENT_AUTH_MECHANISM_PLAIN: &str = "PLAIN";
+
+pub const SERVER_AUTH_FAILED_CMD: &str = "AUTH_FAILED";
+pub const SERVER_AUTH_FAILED_PREFIX: &str = "AUTH_FAILED "; // SPACE
+
+pub const SERVER_SHUTDOWN_CMD: &str = "SERVER_SHUTDOWN";
+pub const SERVER_SHUTDOWN_PREFIX: &str = "SERVER_SHUTDOWN "; // SPACE
+
+pub const CLIENT_MSG_CMD: &str = "MSG";
+pub const CLIENT_MSG_PREFIX: &str = "MSG "; // SPACE
+
+pub const SERVER_DM_CMD: &str = "DM";
+pub const SERVER_DM_PREFIX: &str = "DM "; // SPACE
+
+pub const CLIENT_WHO_CMD: &str = "WHO";
+pub const CLIENT_WHO_PREFIX: &str = "WHO";
+
+pub const SERVER_USERS_CMD: &str = "USERS";
+pub const SERVER_USERS_PREFIX: &str = "USERS "; // SPACE
+
+// Event tags used by the pipe-delimited `common::tcp_message` wire format.
+pub const SERVER_EVENT_OK: &str = "OK";
+pub const SERVER_EVENT_ERR: &str = "ERR";
+pub const SERVER_EVENT_USER_JOINED: &str = "JOINED";
+pub const SERVER_EVENT_USER_LEFT: &str = "LEFT";
+pub const SERVER_EVENT_BROADCAST: &str = "BROADCAST";
+pub const SERVER_EVENT_HISTORY_START: &str = "HISTORY_START";
+pub const SERVER_EVENT_HISTORY_END: &str = "HISTORY_END";
+pub const SERVER_EVENT_PING: &str = "PING";
+pub const CLIENT_EVENT_PONG: &str = "PONG";
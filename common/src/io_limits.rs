@@ -0,0 +1,232 @@
+//! Bounded line reading for this crate's newline-delimited wire formats.
+//!
+//! `tokio::io::AsyncBufReadExt::read_line` grows its buffer until it finds a
+//! newline or hits EOF, with no way to cap how much it reads along the way:
+//! a peer that never sends `\n` makes it accumulate unbounded memory before
+//! any length check a caller performs afterward ever runs. [`LimitedLineReader`]
+//! enforces [`MAX_LINE_LENGTH`] and [`READ_TIMEOUT`] directly in the read
+//! loop instead, so an over-length or stalled line is rejected before it
+//! grows the buffer rather than after.
+
+use std::time::Duration;
+
+use thiserror::Error;
+use tokio::{
+    io::{AsyncBufRead, AsyncBufReadExt},
+    time::timeout,
+};
+
+use crate::security::{MAX_LINE_LENGTH, READ_TIMEOUT};
+
+/// Error returned by [`LimitedLineReader::read_line`].
+#[derive(Debug, Error)]
+pub enum LineReadError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("read timed out after {0:?}")]
+    Timeout(Duration),
+    #[error("line exceeded the {0} byte limit")]
+    LineTooLong(usize),
+    #[error("line was not valid UTF-8")]
+    InvalidUtf8,
+}
+
+/// Wraps any `AsyncBufRead` (e.g. a `tokio::io::BufReader` over a socket)
+/// and reads newline-terminated lines with a byte-accumulation cap and a
+/// per-read timeout enforced up front, instead of after the whole line has
+/// already been buffered.
+///
+/// Defaults to [`MAX_LINE_LENGTH`]/[`READ_TIMEOUT`]; use
+/// [`with_limits`](Self::with_limits) to override either for tests or a
+/// caller with different requirements.
+pub struct LimitedLineReader<R> {
+    inner: R,
+    max_len: usize,
+    read_timeout: Duration,
+}
+
+impl<R: AsyncBufRead + Unpin> LimitedLineReader<R> {
+    #[must_use]
+    pub fn new(inner: R) -> Self {
+        Self::with_limits(inner, MAX_LINE_LENGTH, READ_TIMEOUT)
+    }
+
+    #[must_use]
+    pub fn with_limits(inner: R, max_len: usize, read_timeout: Duration) -> Self {
+        Self {
+            inner,
+            max_len,
+            read_timeout,
+        }
+    }
+
+    /// Reads one newline-terminated line into `buf` (appending, like
+    /// [`AsyncBufReadExt::read_line`]), returning the number of bytes read
+    /// (including the newline), or `0` at EOF.
+    ///
+    /// As soon as accumulating the next chunk (up to and including its `\n`,
+    /// if it has one) would exceed `max_len`, returns [`LineReadError::LineTooLong`]
+    /// without appending anything further to `buf`; the caller should treat
+    /// this as fatal for the connection; unlike a normal read, the
+    /// oversized line's trailing bytes are left unconsumed in `inner`
+    /// rather than silently skipped, so the protocol framing is no longer
+    /// trustworthy after this point.
+    pub async fn read_line(&mut self, buf: &mut String) -> Result<usize, LineReadError> {
+        match timeout(self.read_timeout, self.read_line_inner()).await {
+            Ok(result) => {
+                let bytes = result?;
+                if bytes.is_empty() {
+                    return Ok(0);
+                }
+                let text = std::str::from_utf8(&bytes).map_err(|_| LineReadError::InvalidUtf8)?;
+                buf.push_str(text);
+                Ok(bytes.len())
+            }
+            Err(_) => Err(LineReadError::Timeout(self.read_timeout)),
+        }
+    }
+
+    async fn read_line_inner(&mut self) -> Result<Vec<u8>, LineReadError> {
+        let mut line = Vec::new();
+
+        loop {
+            let available = self.inner.fill_buf().await?;
+            if available.is_empty() {
+                return Ok(line);
+            }
+
+            let newline_pos = available.iter().position(|&b| b == b'\n');
+            let take = newline_pos.map_or(available.len(), |pos| pos + 1);
+
+            if line.len() + take > self.max_len {
+                return Err(LineReadError::LineTooLong(self.max_len));
+            }
+
+            line.extend_from_slice(&available[..take]);
+            self.inner.consume(take);
+
+            if newline_pos.is_some() {
+                return Ok(line);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use tokio::io::BufReader;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_read_line_under_limit() {
+        let data = b"hello\nworld\n".as_slice();
+        let mut reader = LimitedLineReader::with_limits(BufReader::new(data), 100, Duration::from_secs(1));
+
+        let mut buf = String::new();
+        let n = reader.read_line(&mut buf).await.expect("should read");
+        assert_eq!(n, 6);
+        assert_eq!(buf, "hello\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_line_exactly_at_limit() {
+        // "abcd\n" is exactly 5 bytes, matching a 5-byte cap.
+        let data = b"abcd\n".as_slice();
+        let mut reader = LimitedLineReader::with_limits(BufReader::new(data), 5, Duration::from_secs(1));
+
+        let mut buf = String::new();
+        let n = reader.read_line(&mut buf).await.expect("exactly-at-limit should succeed");
+        assert_eq!(n, 5);
+        assert_eq!(buf, "abcd\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_line_one_over_limit_errors() {
+        // "abcde\n" is 6 bytes, one over a 5-byte cap.
+        let data = b"abcde\n".as_slice();
+        let mut reader = LimitedLineReader::with_limits(BufReader::new(data), 5, Duration::from_secs(1));
+
+        let mut buf = String::new();
+        let err = reader.read_line(&mut buf).await.expect_err("should reject oversized line");
+        assert!(matches!(err, LineReadError::LineTooLong(5)));
+        assert!(buf.is_empty(), "buffer must not grow past the limit");
+    }
+
+    #[tokio::test]
+    async fn test_read_line_never_terminated_does_not_exhaust_memory() {
+        // No newline anywhere in the input; the limit must still trip
+        // rather than buffering the whole unterminated stream.
+        let data = vec![b'x'; 10_000];
+        let mut reader = LimitedLineReader::with_limits(BufReader::new(data.as_slice()), 100, Duration::from_secs(1));
+
+        let mut buf = String::new();
+        let err = reader.read_line(&mut buf).await.expect_err("should reject unterminated oversized input");
+        assert!(matches!(err, LineReadError::LineTooLong(100)));
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_line_split_across_reads() {
+        // tokio::io::BufReader's internal buffer is much larger than this
+        // input, but exercising a reader whose `fill_buf` only ever
+        // surfaces a few bytes at a time (as a slow/chunked socket would)
+        // is the scenario this type exists for; wrapping the full byte
+        // slice in a small-capacity `BufReader` forces
+        // `poll_fill_buf` to be called multiple times per line.
+        let data = b"partial|line\nsecond\n".as_slice();
+        let chunked = tokio::io::BufReader::with_capacity(4, data);
+        let mut reader = LimitedLineReader::with_limits(chunked, 100, Duration::from_secs(1));
+
+        let mut buf = String::new();
+        let n = reader.read_line(&mut buf).await.expect("should read across multiple fills");
+        assert_eq!(n, 13);
+        assert_eq!(buf, "partial|line\n");
+
+        buf.clear();
+        let n = reader.read_line(&mut buf).await.expect("should read second line");
+        assert_eq!(n, 7);
+        assert_eq!(buf, "second\n");
+    }
+
+    #[tokio::test]
+    async fn test_read_line_eof_returns_zero() {
+        let data = b"".as_slice();
+        let mut reader = LimitedLineReader::with_limits(BufReader::new(data), 100, Duration::from_secs(1));
+
+        let mut buf = String::new();
+        let n = reader.read_line(&mut buf).await.expect("EOF is not an error");
+        assert_eq!(n, 0);
+        assert!(buf.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_read_line_times_out_on_stalled_source() {
+        use std::pin::Pin;
+        use std::task::{Context, Poll};
+
+        struct NeverReady;
+        impl AsyncBufRead for NeverReady {
+            fn poll_fill_buf(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<&[u8]>> {
+                Poll::Pending
+            }
+            fn consume(self: Pin<&mut Self>, _amt: usize) {}
+        }
+        impl tokio::io::AsyncRead for NeverReady {
+            fn poll_read(
+                self: Pin<&mut Self>,
+                _cx: &mut Context<'_>,
+                _buf: &mut tokio::io::ReadBuf<'_>,
+            ) -> Poll<std::io::Result<()>> {
+                Poll::Pending
+            }
+        }
+
+        let mut reader = LimitedLineReader::with_limits(NeverReady, 100, Duration::from_millis(20));
+        let mut buf = String::new();
+        let err = reader.read_line(&mut buf).await.expect_err("should time out");
+        assert!(matches!(err, LineReadError::Timeout(_)));
+    }
+}
@@ -3,7 +3,15 @@
 //! Provides functions for sanitizing user input before logging,
 //! and security-related constants.
 
-use std::time::Duration;
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    time::{Duration, Instant},
+};
+
+use parking_lot::Mutex;
+use thiserror::Error;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// Maximum allowed message length in bytes.
 pub const MAX_MESSAGE_LENGTH: usize = 4096;
@@ -23,6 +31,13 @@ pub const MAX_MESSAGES_PER_SECOND: u32 = 10;
 /// Rate limit: burst capacity for message rate limiting.
 pub const MESSAGE_BURST_CAPACITY: u32 = 20;
 
+/// Rate limit: maximum `AUTH` attempts per second per connection, to resist
+/// password guessing against the Argon2 credential store.
+pub const MAX_AUTH_ATTEMPTS_PER_SECOND: u32 = 1;
+
+/// Rate limit: burst capacity for `AUTH` attempts.
+pub const AUTH_ATTEMPT_BURST_CAPACITY: u32 = 3;
+
 /// Sanitizes a string for safe logging by escaping control characters.
 ///
 /// This prevents log injection attacks where malicious input could:
@@ -41,38 +56,538 @@ pub const MESSAGE_BURST_CAPACITY: u32 = 20;
 /// ```
 #[must_use]
 pub fn sanitize_for_log(s: &str) -> String {
+    let escaped = sanitize_bytes(s.as_bytes());
+    String::from_utf8(escaped)
+        .expect("sanitize_bytes only copies whole UTF-8 sequences verbatim or substitutes ASCII-safe escapes")
+}
+
+/// Sentinel in [`ESCAPE_TABLE`] for a byte that passes through unchanged.
+const VERBATIM: u8 = 255;
+/// Sentinel in [`ESCAPE_TABLE`] for a byte that must be hex-escaped (`\xNN`).
+const HEX: u8 = 254;
+
+/// Fixed replacement text for the handful of control bytes common enough in
+/// practice to get a short escape instead of a generic `\xNN`, indexed by
+/// the small values [`ESCAPE_TABLE`] maps them to.
+const REPLACEMENTS: [&str; 4] = ["\\n", "\\r", "\\t", "\\0"];
+
+/// 256-entry lookup table mapping each possible byte to [`VERBATIM`],
+/// [`HEX`], or an index into [`REPLACEMENTS`]. Built once at compile time so
+/// the hot path is a single array index rather than a branch-per-character
+/// match.
+static ESCAPE_TABLE: [u8; 256] = build_escape_table();
+
+const fn build_escape_table() -> [u8; 256] {
+    let mut table = [VERBATIM; 256];
+    let mut i = 0usize;
+    while i < 256 {
+        if i < 0x20 || i == 0x7f {
+            table[i] = HEX;
+        }
+        i += 1;
+    }
+    table[b'\n' as usize] = 0;
+    table[b'\r' as usize] = 1;
+    table[b'\t' as usize] = 2;
+    table[0] = 3;
+    table
+}
+
+/// Number of bytes an AVX2 chunk scan covers at a time.
+#[cfg(target_arch = "x86_64")]
+const AVX2_CHUNK_LEN: usize = 32;
+/// Number of bytes an SSE2 chunk scan covers at a time.
+#[cfg(target_arch = "x86_64")]
+const SSE2_CHUNK_LEN: usize = 16;
+
+/// Escapes control bytes (everything [`ESCAPE_TABLE`] doesn't map to
+/// [`VERBATIM`]) in `bytes`, returning the escaped bytes. Non-control,
+/// non-ASCII bytes (i.e. any byte of a multi-byte UTF-8 sequence) are always
+/// [`VERBATIM`], so multi-byte sequences always survive intact.
+///
+/// Dispatches to the widest vectorized scanner the running CPU supports,
+/// falling back to a scalar byte-at-a-time loop for the remainder and, on
+/// non-x86_64 targets, for the whole input.
+fn sanitize_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len());
+    #[cfg_attr(not(target_arch = "x86_64"), allow(unused_mut))]
+    let mut consumed = 0;
+
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            consumed = scan_chunks(bytes, &mut out, AVX2_CHUNK_LEN, |chunk| unsafe {
+                simd::chunk_needs_escape_avx2(chunk)
+            });
+        } else if is_x86_feature_detected!("sse2") {
+            consumed = scan_chunks(bytes, &mut out, SSE2_CHUNK_LEN, |chunk| unsafe {
+                simd::chunk_needs_escape_sse2(chunk)
+            });
+        }
+    }
+
+    sanitize_bytes_scalar_into(&bytes[consumed..], &mut out);
+    out
+}
+
+/// Walks `bytes` in `chunk_len`-sized chunks, bulk-copying each chunk that
+/// `needs_escape` reports clean and falling back to the scalar per-byte path
+/// for any chunk that isn't. Returns how many leading bytes were consumed
+/// (always a multiple of `chunk_len`); the caller handles the remainder.
+#[cfg(target_arch = "x86_64")]
+fn scan_chunks(bytes: &[u8], out: &mut Vec<u8>, chunk_len: usize, needs_escape: impl Fn(&[u8]) -> bool) -> usize {
+    let mut i = 0;
+    while i + chunk_len <= bytes.len() {
+        let chunk = &bytes[i..i + chunk_len];
+        if needs_escape(chunk) {
+            sanitize_bytes_scalar_into(chunk, out);
+        } else {
+            out.extend_from_slice(chunk);
+        }
+        i += chunk_len;
+    }
+    i
+}
+
+/// Scalar fallback: escapes `bytes` one at a time via [`ESCAPE_TABLE`],
+/// appending the result to `out`.
+fn sanitize_bytes_scalar_into(bytes: &[u8], out: &mut Vec<u8>) {
     use std::fmt::Write;
 
-    let mut result = String::with_capacity(s.len());
-    for c in s.chars() {
-        match c {
-            '\n' => result.push_str("\\n"),
-            '\r' => result.push_str("\\r"),
-            '\t' => result.push_str("\\t"),
-            '\0' => result.push_str("\\0"),
-            // Escape other control characters as hex
-            c if c.is_control() => {
-                for byte in c.to_string().bytes() {
-                    // Use write! to avoid extra allocation from format!
-                    let _ = write!(result, "\\x{byte:02x}");
-                }
+    for &b in bytes {
+        match ESCAPE_TABLE[b as usize] {
+            VERBATIM => out.push(b),
+            HEX => {
+                // `\xNN` is always ASCII, so writing into a String and
+                // draining its bytes is safe here.
+                let mut hex = String::with_capacity(4);
+                let _ = write!(hex, "\\x{b:02x}");
+                out.extend_from_slice(hex.as_bytes());
             }
-            c => result.push(c),
+            idx => out.extend_from_slice(REPLACEMENTS[idx as usize].as_bytes()),
         }
     }
-    result
 }
 
-/// Truncates a string to a maximum length, appending "..." if truncated.
+/// AVX2/SSE2 chunk scanners: each reports whether a fixed-size chunk
+/// contains any byte [`ESCAPE_TABLE`] doesn't map to [`VERBATIM`], so
+/// [`scan_chunks`] knows whether it can bulk-copy the chunk untouched.
+#[cfg(target_arch = "x86_64")]
+mod simd {
+    use std::arch::x86_64::{
+        __m128i, __m256i, _mm_cmpeq_epi8, _mm_loadu_si128, _mm_min_epu8, _mm_movemask_epi8, _mm_or_si128,
+        _mm_set1_epi8, _mm256_cmpeq_epi8, _mm256_loadu_si256, _mm256_min_epu8, _mm256_movemask_epi8, _mm256_or_si256,
+        _mm256_set1_epi8,
+    };
+
+    use super::AVX2_CHUNK_LEN;
+
+    /// # Safety
+    ///
+    /// Caller must ensure AVX2 is available (e.g. via
+    /// `is_x86_feature_detected!("avx2")`) and that `chunk` is exactly
+    /// [`AVX2_CHUNK_LEN`] bytes long.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn chunk_needs_escape_avx2(chunk: &[u8]) -> bool {
+        debug_assert_eq!(chunk.len(), AVX2_CHUNK_LEN);
+        unsafe {
+            let data: __m256i = _mm256_loadu_si256(chunk.as_ptr().cast());
+            // Control bytes 0x00-0x1f: unsigned min(byte, 0x1f) == byte iff byte <= 0x1f.
+            let low_ctrl_bound = _mm256_set1_epi8(0x1f);
+            let is_low_ctrl = _mm256_cmpeq_epi8(_mm256_min_epu8(data, low_ctrl_bound), data);
+            // DEL (0x7f) is the other control byte outside the 0x00-0x1f run.
+            let del = _mm256_set1_epi8(0x7f_u8 as i8);
+            let is_del = _mm256_cmpeq_epi8(data, del);
+            _mm256_movemask_epi8(_mm256_or_si256(is_low_ctrl, is_del)) != 0
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Caller must ensure SSE2 is available (guaranteed on x86_64 by the
+    /// platform baseline, but checked anyway for parity with the AVX2 path)
+    /// and that `chunk` is exactly 16 bytes long.
+    #[target_feature(enable = "sse2")]
+    pub(super) unsafe fn chunk_needs_escape_sse2(chunk: &[u8]) -> bool {
+        debug_assert_eq!(chunk.len(), 16);
+        unsafe {
+            let data: __m128i = _mm_loadu_si128(chunk.as_ptr().cast());
+            let low_ctrl_bound = _mm_set1_epi8(0x1f);
+            let is_low_ctrl = _mm_cmpeq_epi8(_mm_min_epu8(data, low_ctrl_bound), data);
+            let del = _mm_set1_epi8(0x7f_u8 as i8);
+            let is_del = _mm_cmpeq_epi8(data, del);
+            _mm_movemask_epi8(_mm_or_si128(is_low_ctrl, is_del)) != 0
+        }
+    }
+}
+
+/// Ellipsis appended by [`truncate_for_log`]/[`truncate_for_log_graphemes`]
+/// when truncation happens.
+const ELLIPSIS: &str = "...";
+
+/// Truncates a string to a maximum length in **bytes**, appending `"..."` if
+/// truncated. `max_len` is a byte budget, not a char count: the result's
+/// UTF-8 byte length never exceeds `max_len`, and truncation always falls on
+/// a char boundary (never splitting a multi-byte scalar), since bytes are
+/// accumulated via `char::len_utf8` rather than by byte-slicing. If `max_len`
+/// is too small to fit even the ellipsis, it's omitted entirely rather than
+/// let the result exceed `max_len`.
 ///
 /// Useful for logging potentially large user input without filling logs.
 #[must_use]
 pub fn truncate_for_log(s: &str, max_len: usize) -> String {
     if s.len() <= max_len {
-        s.to_string()
-    } else {
-        let truncated: String = s.chars().take(max_len.saturating_sub(3)).collect();
-        format!("{truncated}...")
+        return s.to_string();
+    }
+
+    if max_len < ELLIPSIS.len() {
+        return truncate_to_byte_budget(s, max_len);
+    }
+
+    let budget = max_len - ELLIPSIS.len();
+    let mut used = 0;
+    let mut end = 0;
+    for (idx, c) in s.char_indices() {
+        let next_used = used + c.len_utf8();
+        if next_used > budget {
+            break;
+        }
+        used = next_used;
+        end = idx + c.len_utf8();
+    }
+
+    format!("{}{ELLIPSIS}", &s[..end])
+}
+
+/// Truncates `s` to at most `max_len` bytes on a char boundary, with no
+/// ellipsis appended. Shared by [`truncate_for_log`] and
+/// [`truncate_for_log_graphemes`] for budgets too small to fit [`ELLIPSIS`].
+fn truncate_to_byte_budget(s: &str, max_len: usize) -> String {
+    let mut end = 0;
+    for (idx, c) in s.char_indices() {
+        if idx + c.len_utf8() > max_len {
+            break;
+        }
+        end = idx + c.len_utf8();
+    }
+    s[..end].to_string()
+}
+
+/// Grapheme-cluster-aware variant of [`truncate_for_log`]: truncates to a
+/// byte budget like [`truncate_for_log`], but never splits a grapheme
+/// cluster (e.g. a combining-mark sequence or an emoji ZWJ sequence) even
+/// when the cluster is itself several Unicode scalars long. Opt into this
+/// over [`truncate_for_log`] when the input is likely to contain such
+/// clusters and a split one would render as mojibake in the log. Like
+/// [`truncate_for_log`], omits the ellipsis rather than exceed `max_len`
+/// when the budget is too small to fit it.
+#[must_use]
+pub fn truncate_for_log_graphemes(s: &str, max_len: usize) -> String {
+    if s.len() <= max_len {
+        return s.to_string();
+    }
+
+    if max_len < ELLIPSIS.len() {
+        return truncate_to_byte_budget(s, max_len);
+    }
+
+    let budget = max_len - ELLIPSIS.len();
+    let mut used = 0;
+    let mut end = 0;
+    for (idx, grapheme) in s.grapheme_indices(true) {
+        let next_used = used + grapheme.len();
+        if next_used > budget {
+            break;
+        }
+        used = next_used;
+        end = idx + grapheme.len();
+    }
+
+    format!("{}{ELLIPSIS}", &s[..end])
+}
+
+/// Error returned by [`normalize_line_ending`]/[`normalize_line_ending_in_place`]
+/// and surfaced by [`split_messages`] when a line contains a bare `\r` not
+/// immediately followed by `\n`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum LineEndingError {
+    #[error("line contains a bare \\r not followed by \\n")]
+    BareCarriageReturn,
+}
+
+/// Strips exactly one trailing line terminator from `line`, treating both
+/// `\r\n` and bare `\n` as valid terminators, and returns the content
+/// without it. A line with no trailing terminator at all is returned
+/// unchanged (callers reading partial/unterminated input should not treat
+/// that as an error by itself; see [`split_messages`]).
+///
+/// Rejects with [`LineEndingError::BareCarriageReturn`] if, after the
+/// terminator is removed, the remaining content still contains a `\r` —
+/// i.e. a carriage return anywhere that wasn't immediately followed by the
+/// `\n` that terminated the line, the way a strict lexer distinguishes a
+/// well-formed CRLF from a bare CR.
+pub fn normalize_line_ending(line: &str) -> Result<&str, LineEndingError> {
+    let stripped = line.strip_suffix("\r\n").or_else(|| line.strip_suffix('\n')).unwrap_or(line);
+
+    if stripped.contains('\r') {
+        return Err(LineEndingError::BareCarriageReturn);
+    }
+
+    Ok(stripped)
+}
+
+/// In-place variant of [`normalize_line_ending`]: truncates `line` to drop
+/// its trailing terminator instead of returning a new borrowed slice.
+pub fn normalize_line_ending_in_place(line: &mut String) -> Result<(), LineEndingError> {
+    let new_len = normalize_line_ending(line)?.len();
+    line.truncate(new_len);
+    Ok(())
+}
+
+/// Iterator over complete, terminator-stripped lines in `s`, as produced by
+/// [`split_messages`]. Splits on `\n` (covering both `\r\n` and bare `\n`
+/// terminators) and runs each complete line through
+/// [`normalize_line_ending`], so a malformed embedded `\r` surfaces as
+/// `Err(LineEndingError::BareCarriageReturn)` for that line without
+/// stopping iteration over the rest.
+///
+/// Any trailing content with no terminator yet (e.g. the start of a line
+/// still arriving over the wire) is never yielded; call
+/// [`SplitMessages::remainder`] after iteration ends to retrieve it.
+#[derive(Debug, Clone)]
+pub struct SplitMessages<'a> {
+    remainder: &'a str,
+}
+
+impl<'a> SplitMessages<'a> {
+    /// Whatever of the original input hasn't been consumed as a complete,
+    /// terminated line yet.
+    #[must_use]
+    pub fn remainder(&self) -> &'a str {
+        self.remainder
+    }
+}
+
+impl<'a> Iterator for SplitMessages<'a> {
+    type Item = Result<&'a str, LineEndingError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let newline_pos = self.remainder.find('\n')?;
+        let raw_line = &self.remainder[..=newline_pos];
+        self.remainder = &self.remainder[newline_pos + 1..];
+        Some(normalize_line_ending(raw_line))
+    }
+}
+
+/// Splits `s` into complete, terminator-stripped message lines. See
+/// [`SplitMessages`] for the terminator/error/partial-line semantics.
+#[must_use]
+pub fn split_messages(s: &str) -> SplitMessages<'_> {
+    SplitMessages { remainder: s }
+}
+
+/// Computes the Levenshtein edit distance between `a` and `b` via a full
+/// dynamic-programming matrix, operating over `char`s rather than bytes so
+/// multibyte input isn't double-counted. Gives up early, returning `None`,
+/// as soon as the minimum distance achievable from the row computed so far
+/// already exceeds `max_distance` — used by [`closest`] to cut the search
+/// short for obviously-unrelated candidates instead of running the full
+/// O(len(a) * len(b)) matrix every time.
+fn levenshtein_distance(a: &str, b: &str, max_distance: usize) -> Option<usize> {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    if a.len().abs_diff(b.len()) > max_distance {
+        return None;
+    }
+
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    let mut curr = vec![0usize; b.len() + 1];
+
+    for (i, &ca) in a.iter().enumerate() {
+        curr[0] = i + 1;
+        let mut row_min = curr[0];
+
+        for (j, &cb) in b.iter().enumerate() {
+            let cost = usize::from(ca != cb);
+            curr[j + 1] = (prev[j + 1] + 1).min(curr[j] + 1).min(prev[j] + cost);
+            row_min = row_min.min(curr[j + 1]);
+        }
+
+        if row_min > max_distance {
+            return None;
+        }
+
+        std::mem::swap(&mut prev, &mut curr);
+    }
+
+    let distance = prev[b.len()];
+    (distance <= max_distance).then_some(distance)
+}
+
+/// Finds whichever of `candidates` is closest to `candidate` by Levenshtein
+/// edit distance, for suggesting a likely-intended slash command or
+/// flagging a near-collision with a reserved/blocklisted username. Only
+/// considers matches within roughly one third of the longer string's
+/// length (rounded down, minimum 1 edit) — close enough to plausibly be a
+/// typo, not so far that an unrelated word gets suggested. Ties prefer
+/// whichever candidate appears first.
+#[must_use]
+pub fn closest<'a>(candidate: &str, candidates: &[&'a str]) -> Option<&'a str> {
+    let mut best: Option<(&'a str, usize)> = None;
+
+    for &other in candidates {
+        let threshold = (candidate.chars().count().max(other.chars().count()) / 3).max(1);
+        let Some(distance) = levenshtein_distance(candidate, other, threshold) else {
+            continue;
+        };
+
+        let is_better = match best {
+            None => true,
+            Some((_, best_distance)) => distance < best_distance,
+        };
+        if is_better {
+            best = Some((other, distance));
+        }
+    }
+
+    best.map(|(word, _)| word)
+}
+
+/// Formats [`closest`]'s result as a user-facing hint, e.g.
+/// `` "did you mean `join`?" ``. Returns `None` if nothing in `candidates`
+/// was close enough to suggest.
+#[must_use]
+pub fn closest_msg(candidate: &str, candidates: &[&str]) -> Option<String> {
+    closest(candidate, candidates).map(|word| format!("did you mean `{word}`?"))
+}
+
+/// Classic token-bucket rate limiter: holds a `tokens` count that refills
+/// continuously at `refill_rate` tokens/sec up to `capacity`, and lets a
+/// caller [`try_acquire`](Self::try_acquire) `n` tokens at a time. Unlike
+/// `server::chat::rate_limiter::RateLimiter` (which wraps the `governor`
+/// crate), this is a dependency-light, hand-rolled bucket for callers that
+/// just need [`MAX_MESSAGES_PER_SECOND`]/[`MESSAGE_BURST_CAPACITY`]
+/// enforced without pulling in `governor`.
+#[derive(Debug)]
+pub struct RateLimiter {
+    state: Mutex<BucketState>,
+    capacity: f64,
+    refill_rate: f64,
+}
+
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl RateLimiter {
+    /// Builds a limiter using [`MAX_MESSAGES_PER_SECOND`] and
+    /// [`MESSAGE_BURST_CAPACITY`], starting with a full bucket.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_config(MAX_MESSAGES_PER_SECOND, MESSAGE_BURST_CAPACITY)
+    }
+
+    /// Builds a limiter refilling at `refill_rate` tokens/sec up to
+    /// `capacity`, starting with a full bucket.
+    #[must_use]
+    pub fn with_config(refill_rate: u32, capacity: u32) -> Self {
+        let capacity = f64::from(capacity);
+        Self {
+            state: Mutex::new(BucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+            }),
+            capacity,
+            refill_rate: f64::from(refill_rate),
+        }
+    }
+
+    /// Refills the bucket for elapsed time, then attempts to consume `n`
+    /// tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`Duration`] the caller must wait before `n` tokens
+    /// would be available, without consuming any tokens.
+    pub fn try_acquire(&self, n: u32) -> Result<(), Duration> {
+        let n = f64::from(n);
+        let mut state = self.state.lock();
+
+        let now = Instant::now();
+        let elapsed = now.saturating_duration_since(state.last_refill);
+        state.tokens = (state.tokens + elapsed.as_secs_f64() * self.refill_rate).min(self.capacity);
+        state.last_refill = now;
+
+        if state.tokens >= n {
+            state.tokens -= n;
+            Ok(())
+        } else if self.refill_rate <= 0.0 {
+            Err(Duration::MAX)
+        } else {
+            let deficit = n - state.tokens;
+            Err(Duration::from_secs_f64(deficit / self.refill_rate))
+        }
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A [`RateLimiter`] per key (e.g. a user id or connection id), created
+/// lazily on first use with a shared `refill_rate`/`capacity`. Keeps
+/// per-sender throttling from being reset by whatever churns the key space
+/// (reconnects, new connections) while still only holding one lock per
+/// `try_acquire` call against the registry's map.
+#[derive(Debug)]
+pub struct RateLimiterRegistry<K> {
+    limiters: Mutex<HashMap<K, RateLimiter>>,
+    refill_rate: u32,
+    capacity: u32,
+}
+
+impl<K: Eq + Hash> RateLimiterRegistry<K> {
+    /// Builds a registry whose per-key limiters use
+    /// [`MAX_MESSAGES_PER_SECOND`]/[`MESSAGE_BURST_CAPACITY`].
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_config(MAX_MESSAGES_PER_SECOND, MESSAGE_BURST_CAPACITY)
+    }
+
+    /// Builds a registry whose per-key limiters refill at `refill_rate`
+    /// tokens/sec up to `capacity`.
+    #[must_use]
+    pub fn with_config(refill_rate: u32, capacity: u32) -> Self {
+        Self {
+            limiters: Mutex::new(HashMap::new()),
+            refill_rate,
+            capacity,
+        }
+    }
+
+    /// Attempts to consume `n` tokens from `key`'s bucket, creating it with
+    /// a full bucket on first use.
+    ///
+    /// # Errors
+    ///
+    /// Returns the [`Duration`] until `key`'s bucket would have `n` tokens.
+    pub fn try_acquire(&self, key: K, n: u32) -> Result<(), Duration> {
+        let mut limiters = self.limiters.lock();
+        let limiter = limiters
+            .entry(key)
+            .or_insert_with(|| RateLimiter::with_config(self.refill_rate, self.capacity));
+        limiter.try_acquire(n)
+    }
+}
+
+impl<K: Eq + Hash> Default for RateLimiterRegistry<K> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -117,6 +632,68 @@ mod tests {
         assert_eq!(sanitize_for_log("Ã©moji ðŸŽ‰"), "Ã©moji ðŸŽ‰");
     }
 
+    #[test]
+    fn test_sanitize_del_byte() {
+        assert_eq!(sanitize_for_log("a\x7fb"), "a\\x7fb");
+    }
+
+    #[test]
+    fn test_sanitize_generic_control_byte() {
+        assert_eq!(sanitize_for_log("a\x01b"), "a\\x01b");
+    }
+
+    #[test]
+    fn test_sanitize_long_clean_input_spans_simd_chunks() {
+        // Longer than an AVX2 chunk (32 bytes) so the vectorized scanner,
+        // not just the scalar tail, actually runs over clean data.
+        let input = "a".repeat(100);
+        assert_eq!(sanitize_for_log(&input), input);
+    }
+
+    #[test]
+    fn test_sanitize_control_byte_inside_long_input() {
+        // A control byte landing mid-chunk must still force that chunk
+        // through the scalar path instead of being bulk-copied.
+        let mut input = "a".repeat(50);
+        input.push('\n');
+        input.push_str(&"b".repeat(50));
+
+        let mut expected = "a".repeat(50);
+        expected.push_str("\\n");
+        expected.push_str(&"b".repeat(50));
+
+        assert_eq!(sanitize_for_log(&input), expected);
+    }
+
+    #[test]
+    fn test_sanitize_scalar_and_dispatched_paths_agree() {
+        // Deterministic pseudo-random xorshift generator (no `rand`
+        // dependency) producing a mix of ASCII, control bytes, and
+        // multi-byte UTF-8, to fuzz-check that whichever SIMD path the
+        // running CPU dispatches to agrees byte-for-byte with the pure
+        // scalar fallback.
+        let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+
+        let pool = ['a', 'z', '0', '\n', '\r', '\t', '\0', '\x01', '\x7f', '\u{e9}', '\u{4f60}', '\u{1f389}', ' '];
+
+        for _ in 0..200 {
+            let len = (next() % 200) as usize;
+            let input: String = (0..len).map(|_| pool[(next() % pool.len() as u64) as usize]).collect();
+
+            let mut scalar_out = Vec::with_capacity(input.len());
+            sanitize_bytes_scalar_into(input.as_bytes(), &mut scalar_out);
+            let scalar = String::from_utf8(scalar_out).expect("scalar path only emits valid UTF-8");
+
+            assert_eq!(sanitize_for_log(&input), scalar, "mismatch for input {input:?}");
+        }
+    }
+
     #[test]
     fn test_truncate_short_string() {
         assert_eq!(truncate_for_log("hello", 10), "hello");
@@ -128,4 +705,232 @@ mod tests {
         assert_eq!(truncate_for_log("hello world", 8), "hello...");
         assert_eq!(truncate_for_log("abcdefghij", 6), "abc...");
     }
+
+    #[test]
+    fn test_truncate_never_exceeds_byte_budget() {
+        // Each "\u{e9}" is a 2-byte UTF-8 scalar, so a naive char-count
+        // truncation would blow past a small byte budget.
+        let s = "\u{e9}".repeat(20);
+        let truncated = truncate_for_log(&s, 10);
+        assert!(truncated.len() <= 10, "{truncated:?} exceeds the 10-byte budget");
+    }
+
+    #[test]
+    fn test_truncate_never_splits_a_multibyte_scalar() {
+        let s = "\u{e9}".repeat(20);
+        let truncated = truncate_for_log(&s, 7);
+        // Must still be valid UTF-8 with no partial scalar at the cut point.
+        assert!(truncated.ends_with("..."));
+        assert!(std::str::from_utf8(truncated.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_truncate_emoji_boundary() {
+        let s = "\u{1f600}\u{1f600}\u{1f600}\u{1f600}"; // 4 bytes each
+        let truncated = truncate_for_log(s, 9);
+        assert!(truncated.len() <= 9);
+        assert!(truncated.ends_with("..."));
+    }
+
+    #[test]
+    fn test_truncate_graphemes_keeps_combining_mark_intact() {
+        // 'e' + COMBINING ACUTE ACCENT (U+0301) is one grapheme cluster but
+        // two Unicode scalars; a scalar-level truncation could split them.
+        let s = "e\u{301}e\u{301}e\u{301}e\u{301}e\u{301}";
+        let truncated = truncate_for_log_graphemes(s, 7);
+
+        assert!(truncated.len() <= 7);
+        assert!(truncated.ends_with("..."));
+        // The kept prefix must consist only of whole `e\u{301}` clusters.
+        let prefix = truncated.trim_end_matches('.');
+        assert_eq!(prefix.len() % "e\u{301}".len(), 0);
+    }
+
+    #[test]
+    fn test_truncate_graphemes_short_string_unchanged() {
+        assert_eq!(truncate_for_log_graphemes("hello", 10), "hello");
+    }
+
+    #[test]
+    fn test_truncate_budget_too_small_for_ellipsis_omits_it() {
+        // max_len < ELLIPSIS.len() (3): appending "..." would itself blow
+        // the budget, so the result must be a bare truncation instead.
+        for max_len in 0..3 {
+            let truncated = truncate_for_log("hello world", max_len);
+            assert!(
+                truncated.len() <= max_len,
+                "truncate_for_log(_, {max_len}) = {truncated:?} exceeds the budget"
+            );
+            assert!(!truncated.contains('.'));
+        }
+    }
+
+    #[test]
+    fn test_truncate_graphemes_budget_too_small_for_ellipsis_omits_it() {
+        for max_len in 0..3 {
+            let truncated = truncate_for_log_graphemes("hello world", max_len);
+            assert!(
+                truncated.len() <= max_len,
+                "truncate_for_log_graphemes(_, {max_len}) = {truncated:?} exceeds the budget"
+            );
+            assert!(!truncated.contains('.'));
+        }
+    }
+
+    #[test]
+    fn test_truncate_budget_exactly_ellipsis_len() {
+        // max_len == ELLIPSIS.len() (3) is the smallest budget the
+        // ellipsis-appending path can still satisfy: no room for any
+        // content, so the result is the bare ellipsis, exactly at budget.
+        let truncated = truncate_for_log("hello world", 3);
+        assert_eq!(truncated, "...");
+        assert!(truncated.len() <= 3);
+    }
+
+    #[test]
+    fn test_normalize_crlf() {
+        assert_eq!(normalize_line_ending("hello\r\n"), Ok("hello"));
+    }
+
+    #[test]
+    fn test_normalize_bare_lf() {
+        assert_eq!(normalize_line_ending("hello\n"), Ok("hello"));
+    }
+
+    #[test]
+    fn test_normalize_no_terminator_returned_unchanged() {
+        assert_eq!(normalize_line_ending("hello"), Ok("hello"));
+    }
+
+    #[test]
+    fn test_normalize_embedded_bare_cr_rejected() {
+        assert_eq!(
+            normalize_line_ending("hel\rlo\n"),
+            Err(LineEndingError::BareCarriageReturn)
+        );
+        assert_eq!(normalize_line_ending("hello\r"), Err(LineEndingError::BareCarriageReturn));
+    }
+
+    #[test]
+    fn test_normalize_line_ending_in_place() {
+        let mut line = "hello\r\n".to_string();
+        normalize_line_ending_in_place(&mut line).expect("should normalize");
+        assert_eq!(line, "hello");
+    }
+
+    #[test]
+    fn test_normalize_line_ending_in_place_rejects_bare_cr() {
+        let mut line = "hel\rlo\n".to_string();
+        let err = normalize_line_ending_in_place(&mut line).expect_err("should reject");
+        assert_eq!(err, LineEndingError::BareCarriageReturn);
+    }
+
+    #[test]
+    fn test_split_messages_crlf_and_lf() {
+        let messages: Vec<_> = split_messages("one\r\ntwo\nthree\r\n").collect();
+        assert_eq!(messages, vec![Ok("one"), Ok("two"), Ok("three")]);
+    }
+
+    #[test]
+    fn test_split_messages_embedded_bare_cr_surfaces_error_but_continues() {
+        let messages: Vec<_> = split_messages("go\rod\nfine\n").collect();
+        assert_eq!(messages, vec![Err(LineEndingError::BareCarriageReturn), Ok("fine")]);
+    }
+
+    #[test]
+    fn test_split_messages_trailing_partial_line_not_yielded() {
+        let mut iter = split_messages("complete\nincomplete");
+        assert_eq!(iter.next(), Some(Ok("complete")));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.remainder(), "incomplete");
+    }
+
+    #[test]
+    fn test_closest_exact_match() {
+        assert_eq!(closest("join", &["join", "leave", "history"]), Some("join"));
+    }
+
+    #[test]
+    fn test_closest_one_edit_typo() {
+        // Single substitution typos, one edit away from their intended match.
+        assert_eq!(closest("jojn", &["join", "leave", "history"]), Some("join"));
+        assert_eq!(closest("histary", &["join", "leave", "history"]), Some("history"));
+    }
+
+    #[test]
+    fn test_closest_rejects_below_threshold() {
+        // "xyz" is nowhere near any candidate within a third of the longer
+        // string's length, so nothing should be suggested.
+        assert_eq!(closest("xyz", &["join", "leave", "history"]), None);
+    }
+
+    #[test]
+    fn test_closest_empty_candidates() {
+        assert_eq!(closest("join", &[]), None);
+    }
+
+    #[test]
+    fn test_closest_msg_formats_hint() {
+        assert_eq!(closest_msg("jojn", &["join", "leave"]), Some("did you mean `join`?".to_string()));
+        assert_eq!(closest_msg("xyz", &["join", "leave"]), None);
+    }
+
+    #[test]
+    fn test_rate_limiter_burst_consumption() {
+        let limiter = RateLimiter::with_config(10, 5);
+
+        for _ in 0..5 {
+            assert!(limiter.try_acquire(1).is_ok());
+        }
+        assert!(limiter.try_acquire(1).is_err());
+    }
+
+    #[test]
+    fn test_rate_limiter_steady_state_throttling() {
+        let limiter = RateLimiter::with_config(10, 1);
+
+        assert!(limiter.try_acquire(1).is_ok());
+        let err = limiter.try_acquire(1).expect_err("bucket should be empty");
+        assert!(err <= Duration::from_millis(110), "unexpectedly long wait: {err:?}");
+        assert!(err > Duration::ZERO);
+    }
+
+    #[test]
+    fn test_rate_limiter_idle_refill() {
+        let limiter = RateLimiter::with_config(100, 10);
+
+        for _ in 0..10 {
+            assert!(limiter.try_acquire(1).is_ok());
+        }
+        assert!(limiter.try_acquire(1).is_err());
+
+        std::thread::sleep(Duration::from_millis(150));
+
+        assert!(limiter.try_acquire(1).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limiter_zero_refill_rate_never_recovers() {
+        let limiter = RateLimiter::with_config(0, 1);
+
+        assert!(limiter.try_acquire(1).is_ok());
+        assert_eq!(limiter.try_acquire(1), Err(Duration::MAX));
+    }
+
+    #[test]
+    fn test_rate_limiter_registry_tracks_keys_independently() {
+        let registry: RateLimiterRegistry<&str> = RateLimiterRegistry::with_config(10, 1);
+
+        assert!(registry.try_acquire("alice", 1).is_ok());
+        assert!(registry.try_acquire("alice", 1).is_err());
+
+        // A different key gets its own, untouched bucket.
+        assert!(registry.try_acquire("bob", 1).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limiter_default_impl() {
+        let limiter = RateLimiter::default();
+        assert!(limiter.try_acquire(1).is_ok());
+    }
 }
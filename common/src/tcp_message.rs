@@ -7,7 +7,6 @@
 //! - 2nd: reason (error), username (join/left/broadcast)
 //! - 3rd: message (broadcast only)
 
-use stringzilla::sz;
 use thiserror::Error;
 
 use crate::consts;
@@ -15,6 +14,78 @@ use crate::consts;
 /// Separator for wire protocol fields
 pub const FIELD_SEPARATOR: &str = "|";
 
+/// Error returned when a field can't be unescaped.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum UnescapeError {
+    #[error("dangling escape character at end of field")]
+    DanglingEscape,
+    #[error("invalid escape sequence: \\{0}")]
+    InvalidEscape(char),
+}
+
+/// Escapes `\`, `|`, and newline in `field` so it can be safely joined with
+/// other fields using [`FIELD_SEPARATOR`] without corrupting the split on
+/// decode. Reversed by [`unescape_field`].
+#[must_use]
+pub fn escape_field(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '|' => escaped.push_str("\\|"),
+            '\n' => escaped.push_str("\\n"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Reverses [`escape_field`], turning `\\`, `\|`, and `\n` escape sequences
+/// back into literal backslash, pipe, and newline characters.
+///
+/// # Errors
+///
+/// Returns an error if `field` ends with a dangling `\` or contains a `\`
+/// followed by anything other than `\`, `|`, or `n`.
+pub fn unescape_field(field: &str) -> Result<String, UnescapeError> {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('\\') => unescaped.push('\\'),
+            Some('|') => unescaped.push('|'),
+            Some('n') => unescaped.push('\n'),
+            Some(other) => return Err(UnescapeError::InvalidEscape(other)),
+            None => return Err(UnescapeError::DanglingEscape),
+        }
+    }
+    Ok(unescaped)
+}
+
+/// Finds the index of the first *unescaped* occurrence of [`FIELD_SEPARATOR`]
+/// in `s`, i.e. a `|` not preceded by an odd run of backslashes. Used to
+/// split wire-format strings into fields without splitting on a `|` that a
+/// field owner escaped via [`escape_field`].
+fn find_unescaped_separator(s: &str) -> Option<usize> {
+    let bytes = s.as_bytes();
+    let mut backslash_run = 0usize;
+    for (i, &b) in bytes.iter().enumerate() {
+        if b == b'\\' {
+            backslash_run += 1;
+        } else {
+            if b == b'|' && backslash_run % 2 == 0 {
+                return Some(i);
+            }
+            backslash_run = 0;
+        }
+    }
+    None
+}
+
 /// Wire protocol encode trait
 pub trait WireEncode {
     /// Encode to wire format bytes
@@ -47,6 +118,21 @@ pub enum ServerMessage {
     UserLeft { username: String },
     /// Broadcast message from a user
     Broadcast { username: String, message: String },
+    /// Marks the start of a history replay, before any replayed `Broadcast`
+    /// frames, so the client can visually delimit scrollback from live
+    /// messages.
+    HistoryStart,
+    /// Marks the end of a history replay.
+    HistoryEnd,
+    /// A bounded batch of recent `(username, message)` pairs replayed to a
+    /// client on join/reconnect. Encodes as [`HistoryStart`](Self::HistoryStart),
+    /// one line per message in the same form as [`Broadcast`](Self::Broadcast),
+    /// then [`HistoryEnd`](Self::HistoryEnd), newline-joined into a single frame.
+    HistoryBatch { messages: Vec<(String, String)> },
+    /// Liveness check; the client echoes `token` back as
+    /// [`ClientMessage::Pong`] so the connection layer can schedule and time
+    /// out a heartbeat.
+    Ping { token: String },
 }
 
 /// Parse error for server messages
@@ -60,18 +146,42 @@ pub enum ServerParseError {
     UnknownEventType(String),
     #[error("missing field: {0}")]
     MissingField(&'static str),
+    #[error("invalid escape sequence in field: {0}")]
+    InvalidEscape(UnescapeError),
 }
 
 impl WireEncode for ServerMessage {
     fn encode(&self) -> Vec<u8> {
         let s = match self {
             Self::Ok => consts::SERVER_EVENT_OK.to_string(),
-            Self::Err { reason } => [consts::SERVER_EVENT_ERR, reason].join(FIELD_SEPARATOR),
-            Self::UserJoined { username } => [consts::SERVER_EVENT_USER_JOINED, username].join(FIELD_SEPARATOR),
-            Self::UserLeft { username } => [consts::SERVER_EVENT_USER_LEFT, username].join(FIELD_SEPARATOR),
-            Self::Broadcast { username, message } => {
-                [consts::SERVER_EVENT_BROADCAST, username, message].join(FIELD_SEPARATOR)
+            Self::Err { reason } => [consts::SERVER_EVENT_ERR, &escape_field(reason)].join(FIELD_SEPARATOR),
+            Self::UserJoined { username } => {
+                [consts::SERVER_EVENT_USER_JOINED, &escape_field(username)].join(FIELD_SEPARATOR)
+            }
+            Self::UserLeft { username } => {
+                [consts::SERVER_EVENT_USER_LEFT, &escape_field(username)].join(FIELD_SEPARATOR)
             }
+            Self::Broadcast { username, message } => [
+                consts::SERVER_EVENT_BROADCAST,
+                &escape_field(username),
+                &escape_field(message),
+            ]
+            .join(FIELD_SEPARATOR),
+            Self::HistoryStart => consts::SERVER_EVENT_HISTORY_START.to_string(),
+            Self::HistoryEnd => consts::SERVER_EVENT_HISTORY_END.to_string(),
+            Self::HistoryBatch { messages } => {
+                let mut lines = Vec::with_capacity(messages.len().saturating_add(2));
+                lines.push(consts::SERVER_EVENT_HISTORY_START.to_string());
+                for (username, message) in messages {
+                    lines.push(
+                        [consts::SERVER_EVENT_BROADCAST, &escape_field(username), &escape_field(message)]
+                            .join(FIELD_SEPARATOR),
+                    );
+                }
+                lines.push(consts::SERVER_EVENT_HISTORY_END.to_string());
+                lines.join("\n")
+            }
+            Self::Ping { token } => [consts::SERVER_EVENT_PING, &escape_field(token)].join(FIELD_SEPARATOR),
         };
         s.into_bytes()
     }
@@ -88,8 +198,13 @@ impl WireDecode for ServerMessage {
             return Err(ServerParseError::Empty);
         }
 
-        // Find first separator
-        let (event_type, rest) = match sz::find(trimmed, FIELD_SEPARATOR) {
+        let lines: Vec<&str> = trimmed.split('\n').collect();
+        if lines.len() > 1 {
+            return Self::decode_history_batch(&lines);
+        }
+
+        // Find first unescaped separator
+        let (event_type, rest) = match find_unescaped_separator(trimmed) {
             Some(idx) => (
                 trimmed.get(..idx).ok_or(ServerParseError::Empty)?,
                 trimmed.get(idx.saturating_add(1)..),
@@ -100,37 +215,72 @@ impl WireDecode for ServerMessage {
         match event_type.to_uppercase().as_str() {
             consts::SERVER_EVENT_OK => Ok(Self::Ok),
             consts::SERVER_EVENT_ERR => {
-                let reason = rest.ok_or(ServerParseError::MissingField("reason"))?.to_string();
+                let raw = rest.ok_or(ServerParseError::MissingField("reason"))?;
+                let reason = unescape_field(raw).map_err(ServerParseError::InvalidEscape)?;
                 Ok(Self::Err { reason })
             }
             consts::SERVER_EVENT_USER_JOINED => {
-                let username = rest.ok_or(ServerParseError::MissingField("username"))?.to_string();
+                let raw = rest.ok_or(ServerParseError::MissingField("username"))?;
+                let username = unescape_field(raw).map_err(ServerParseError::InvalidEscape)?;
                 Ok(Self::UserJoined { username })
             }
             consts::SERVER_EVENT_USER_LEFT => {
-                let username = rest.ok_or(ServerParseError::MissingField("username"))?.to_string();
+                let raw = rest.ok_or(ServerParseError::MissingField("username"))?;
+                let username = unescape_field(raw).map_err(ServerParseError::InvalidEscape)?;
                 Ok(Self::UserLeft { username })
             }
             consts::SERVER_EVENT_BROADCAST => {
                 let rest = rest.ok_or(ServerParseError::MissingField("username"))?;
-                // Find second separator for message
-                let (username, message) = match sz::find(rest, FIELD_SEPARATOR) {
+                // Find second unescaped separator for message
+                let (username, message) = match find_unescaped_separator(rest) {
                     Some(idx) => (
                         rest.get(..idx).ok_or(ServerParseError::MissingField("username"))?,
                         rest.get(idx.saturating_add(1)..).unwrap_or(""),
                     ),
                     None => (rest, ""),
                 };
-                Ok(Self::Broadcast {
-                    username: username.to_string(),
-                    message: message.to_string(),
-                })
+                let username = unescape_field(username).map_err(ServerParseError::InvalidEscape)?;
+                let message = unescape_field(message).map_err(ServerParseError::InvalidEscape)?;
+                Ok(Self::Broadcast { username, message })
+            }
+            consts::SERVER_EVENT_HISTORY_START => Ok(Self::HistoryStart),
+            consts::SERVER_EVENT_HISTORY_END => Ok(Self::HistoryEnd),
+            consts::SERVER_EVENT_PING => {
+                let raw = rest.ok_or(ServerParseError::MissingField("token"))?;
+                let token = unescape_field(raw).map_err(ServerParseError::InvalidEscape)?;
+                Ok(Self::Ping { token })
             }
             _ => Err(ServerParseError::UnknownEventType(event_type.to_string())),
         }
     }
 }
 
+impl ServerMessage {
+    /// Decodes a multi-line [`Self::HistoryBatch`] frame: `lines[0]` must be
+    /// [`consts::SERVER_EVENT_HISTORY_START`], `lines[last]` must be
+    /// [`consts::SERVER_EVENT_HISTORY_END`], and everything in between must
+    /// decode as a [`Self::Broadcast`].
+    fn decode_history_batch(lines: &[&str]) -> Result<Self, ServerParseError> {
+        let first = (*lines.first().ok_or(ServerParseError::Empty)?).trim();
+        if !first.eq_ignore_ascii_case(consts::SERVER_EVENT_HISTORY_START) {
+            return Err(ServerParseError::MissingField("HISTORY_START"));
+        }
+        let last = (*lines.last().ok_or(ServerParseError::Empty)?).trim();
+        if !last.eq_ignore_ascii_case(consts::SERVER_EVENT_HISTORY_END) {
+            return Err(ServerParseError::MissingField("HISTORY_END"));
+        }
+
+        let mut messages = Vec::with_capacity(lines.len().saturating_sub(2));
+        for line in &lines[1..lines.len().saturating_sub(1)] {
+            match Self::decode(line.trim().as_bytes())? {
+                Self::Broadcast { username, message } => messages.push((username, message)),
+                _ => return Err(ServerParseError::UnknownEventType((*line).to_string())),
+            }
+        }
+        Ok(Self::HistoryBatch { messages })
+    }
+}
+
 impl std::fmt::Display for ServerMessage {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let bytes = self.encode();
@@ -148,6 +298,11 @@ pub enum ClientMessage {
     Send { message: String },
     /// Leave the chat
     Leave,
+    /// Request up to `limit` recent broadcasts, replayed as a
+    /// [`ServerMessage::HistoryBatch`].
+    History { limit: u32 },
+    /// Echoes the token from a [`ServerMessage::Ping`] back to the server.
+    Pong { token: String },
 }
 
 /// Parse error for client messages
@@ -161,14 +316,20 @@ pub enum ClientParseError {
     UnknownCommand(String),
     #[error("missing field: {0}")]
     MissingField(&'static str),
+    #[error("invalid escape sequence in field: {0}")]
+    InvalidEscape(UnescapeError),
+    #[error("invalid history limit: {0}")]
+    InvalidLimit(String),
 }
 
 impl WireEncode for ClientMessage {
     fn encode(&self) -> Vec<u8> {
         let s = match self {
-            Self::Join { username } => [consts::CLIENT_JOIN_CMD, username].join(FIELD_SEPARATOR),
-            Self::Send { message } => [consts::CLIENT_SEND_CMD, message].join(FIELD_SEPARATOR),
+            Self::Join { username } => [consts::CLIENT_JOIN_CMD, &escape_field(username)].join(FIELD_SEPARATOR),
+            Self::Send { message } => [consts::CLIENT_SEND_CMD, &escape_field(message)].join(FIELD_SEPARATOR),
             Self::Leave => consts::CLIENT_LEAVE_CMD.to_string(),
+            Self::History { limit } => [consts::CLIENT_HISTORY_CMD, &limit.to_string()].join(FIELD_SEPARATOR),
+            Self::Pong { token } => [consts::CLIENT_EVENT_PONG, &escape_field(token)].join(FIELD_SEPARATOR),
         };
         s.into_bytes()
     }
@@ -185,8 +346,8 @@ impl WireDecode for ClientMessage {
             return Err(ClientParseError::Empty);
         }
 
-        // Find first separator
-        let (command, rest) = match sz::find(trimmed, FIELD_SEPARATOR) {
+        // Find first unescaped separator
+        let (command, rest) = match find_unescaped_separator(trimmed) {
             Some(idx) => (
                 trimmed.get(..idx).ok_or(ClientParseError::Empty)?,
                 trimmed.get(idx.saturating_add(1)..),
@@ -196,20 +357,34 @@ impl WireDecode for ClientMessage {
 
         match command.to_uppercase().as_str() {
             consts::CLIENT_JOIN_CMD => {
-                let username = rest.ok_or(ClientParseError::MissingField("username"))?.to_string();
+                let raw = rest.ok_or(ClientParseError::MissingField("username"))?;
+                let username = unescape_field(raw).map_err(ClientParseError::InvalidEscape)?;
                 if username.is_empty() {
                     return Err(ClientParseError::MissingField("username"));
                 }
                 Ok(Self::Join { username })
             }
             consts::CLIENT_SEND_CMD => {
-                let message = rest.ok_or(ClientParseError::MissingField("message"))?.to_string();
+                let raw = rest.ok_or(ClientParseError::MissingField("message"))?;
+                let message = unescape_field(raw).map_err(ClientParseError::InvalidEscape)?;
                 if message.is_empty() {
                     return Err(ClientParseError::MissingField("message"));
                 }
                 Ok(Self::Send { message })
             }
             consts::CLIENT_LEAVE_CMD => Ok(Self::Leave),
+            consts::CLIENT_HISTORY_CMD => {
+                let raw = rest.ok_or(ClientParseError::MissingField("limit"))?;
+                let limit = raw
+                    .parse::<u32>()
+                    .map_err(|_| ClientParseError::InvalidLimit(raw.to_string()))?;
+                Ok(Self::History { limit })
+            }
+            consts::CLIENT_EVENT_PONG => {
+                let raw = rest.ok_or(ClientParseError::MissingField("token"))?;
+                let token = unescape_field(raw).map_err(ClientParseError::InvalidEscape)?;
+                Ok(Self::Pong { token })
+            }
             _ => Err(ClientParseError::UnknownCommand(command.to_string())),
         }
     }
@@ -448,6 +623,204 @@ mod tests {
         let decoded = ClientMessage::decode(&encoded).expect("should roundtrip");
         assert_eq!(original, decoded);
     }
+
+    // Escaping tests
+
+    #[test]
+    fn test_escape_field_roundtrip() {
+        for field in ["plain", "has|pipe", "has\\backslash", "has\nnewline", "a|b\\c\nd"] {
+            let escaped = escape_field(field);
+            let unescaped = unescape_field(&escaped).expect("should unescape");
+            assert_eq!(unescaped, field);
+        }
+    }
+
+    #[test]
+    fn test_unescape_field_dangling_escape() {
+        let result = unescape_field("oops\\");
+        assert_eq!(result, Err(UnescapeError::DanglingEscape));
+    }
+
+    #[test]
+    fn test_unescape_field_invalid_escape() {
+        let result = unescape_field("oops\\q");
+        assert_eq!(result, Err(UnescapeError::InvalidEscape('q')));
+    }
+
+    #[test]
+    fn test_roundtrip_server_broadcast_with_pipe_in_username() {
+        let original = ServerMessage::Broadcast {
+            username: "al|ice".to_string(),
+            message: "hello|world".to_string(),
+        };
+        let encoded = original.encode();
+        let decoded = ServerMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_roundtrip_server_broadcast_with_backslash_and_newline() {
+        let original = ServerMessage::Broadcast {
+            username: "back\\slash".to_string(),
+            message: "line one\nline two".to_string(),
+        };
+        let encoded = original.encode();
+        let decoded = ServerMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_roundtrip_client_join_with_pipe_in_username() {
+        let original = ClientMessage::Join {
+            username: "al|ice".to_string(),
+        };
+        let encoded = original.encode();
+        let decoded = ClientMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_roundtrip_client_send_with_pipe_backslash_and_newline() {
+        let original = ClientMessage::Send {
+            message: "pipe|backslash\\newline\nend".to_string(),
+        };
+        let encoded = original.encode();
+        let decoded = ClientMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    // History replay tests
+
+    #[test]
+    fn test_client_history_encode() {
+        let msg = ClientMessage::History { limit: 50 };
+        assert_eq!(msg.encode(), b"HISTORY|50");
+    }
+
+    #[test]
+    fn test_roundtrip_client_history() {
+        let original = ClientMessage::History { limit: 50 };
+        let encoded = original.encode();
+        let decoded = ClientMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_client_history_decode_invalid_limit() {
+        let result = ClientMessage::decode(b"HISTORY|not-a-number");
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_server_history_start_end_encode() {
+        assert_eq!(ServerMessage::HistoryStart.encode(), b"HISTORY_START");
+        assert_eq!(ServerMessage::HistoryEnd.encode(), b"HISTORY_END");
+    }
+
+    #[test]
+    fn test_roundtrip_server_history_start_end() {
+        for original in [ServerMessage::HistoryStart, ServerMessage::HistoryEnd] {
+            let encoded = original.encode();
+            let decoded = ServerMessage::decode(&encoded).expect("should roundtrip");
+            assert_eq!(original, decoded);
+        }
+    }
+
+    #[test]
+    fn test_roundtrip_server_history_batch() {
+        let original = ServerMessage::HistoryBatch {
+            messages: vec![
+                ("alice".to_string(), "hi".to_string()),
+                ("bob".to_string(), "pipes|and\\slashes\nhere".to_string()),
+            ],
+        };
+        let encoded = original.encode();
+        let decoded = ServerMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_server_history_batch_encode_framing() {
+        let msg = ServerMessage::HistoryBatch {
+            messages: vec![("alice".to_string(), "hi".to_string())],
+        };
+        let encoded = String::from_utf8(msg.encode()).expect("valid utf8");
+        assert_eq!(encoded, "HISTORY_START\nBROADCAST|alice|hi\nHISTORY_END");
+    }
+
+    #[test]
+    fn test_server_history_batch_empty_roundtrip() {
+        let original = ServerMessage::HistoryBatch { messages: vec![] };
+        let encoded = original.encode();
+        let decoded = ServerMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_server_history_batch_decode_missing_end_marker() {
+        let result = ServerMessage::decode(b"HISTORY_START\nBROADCAST|alice|hi");
+        assert!(result.is_err());
+    }
+
+    // Keepalive tests
+
+    #[test]
+    fn test_server_ping_encode() {
+        let msg = ServerMessage::Ping {
+            token: "abc123".to_string(),
+        };
+        assert_eq!(msg.encode(), b"PING|abc123");
+    }
+
+    #[test]
+    fn test_server_ping_decode_case_insensitive() {
+        let msg = ServerMessage::decode(b"ping|abc123").expect("should decode");
+        assert_eq!(
+            msg,
+            ServerMessage::Ping {
+                token: "abc123".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_server_ping() {
+        let original = ServerMessage::Ping {
+            token: "with|pipe\\and\nnewline".to_string(),
+        };
+        let encoded = original.encode();
+        let decoded = ServerMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
+
+    #[test]
+    fn test_client_pong_encode() {
+        let msg = ClientMessage::Pong {
+            token: "abc123".to_string(),
+        };
+        assert_eq!(msg.encode(), b"PONG|abc123");
+    }
+
+    #[test]
+    fn test_client_pong_decode_case_insensitive() {
+        let msg = ClientMessage::decode(b"pong|abc123").expect("should decode");
+        assert_eq!(
+            msg,
+            ClientMessage::Pong {
+                token: "abc123".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_roundtrip_client_pong() {
+        let original = ClientMessage::Pong {
+            token: "with|pipe\\and\nnewline".to_string(),
+        };
+        let encoded = original.encode();
+        let decoded = ClientMessage::decode(&encoded).expect("should roundtrip");
+        assert_eq!(original, decoded);
+    }
 }
 
 #[cfg(test)]
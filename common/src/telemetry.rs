@@ -1,21 +1,52 @@
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::WithExportConfig;
 use tracing_appender::non_blocking::WorkerGuard;
 use tracing_subscriber::{EnvFilter, Layer, Registry, fmt, layer::SubscriberExt, util::SubscriberInitExt};
 
 use crate::config;
+
+/// Keeps the logging and (optional) OTLP tracing pipelines alive for the
+/// duration of the program.
+///
+/// Must be held until shortly before exit: dropping the `WorkerGuard` flushes
+/// buffered stdout log lines, and [`TelemetryGuard::shutdown`] flushes any
+/// spans still buffered in the OTLP exporter.
+pub struct TelemetryGuard {
+    _log_guard: WorkerGuard,
+    otlp_provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl TelemetryGuard {
+    /// Flushes and shuts down the OTLP tracer provider, if one was
+    /// configured. A no-op when `CHAT_APP_OTLP_ENDPOINT` was unset.
+    pub fn shutdown(&self) {
+        if let Some(provider) = &self.otlp_provider
+            && let Err(e) = provider.shutdown()
+        {
+            eprintln!("Failed to shut down OTLP tracer provider: {e}");
+        }
+    }
+}
+
 /// Initialize the logging/tracing subsystem.
 ///
-/// Returns a `WorkerGuard` that must be kept alive for the duration of the program
-/// to ensure all log messages are flushed.
+/// Returns a [`TelemetryGuard`] that must be kept alive for the duration of
+/// the program to ensure all log messages and spans are flushed.
 ///
 /// The logging format depends on the `APP_ENV` environment variable:
 /// - `production`: JSON format
 /// - other: Pretty format (default)
 ///
+/// When `CHAT_APP_OTLP_ENDPOINT` is set, spans are additionally exported to
+/// an OTLP collector over gRPC alongside the stdout logs. When unset, no
+/// OTLP layer is attached and behavior is unchanged from stdout-only logging.
+///
 /// # Errors
 ///
 /// Returns an error if the tracing subscriber fails to initialize
-/// (e.g., if it has already been initialized).
-pub fn init_logging() -> Result<WorkerGuard, Box<dyn std::error::Error + Send + Sync>> {
+/// (e.g., if it has already been initialized), or if the OTLP pipeline
+/// fails to install.
+pub fn init_logging() -> Result<TelemetryGuard, Box<dyn std::error::Error + Send + Sync>> {
     let _ = config::get_server_tz()?;
     let log_level = config::log_level()?;
     let app_env = config::app_env();
@@ -30,7 +61,24 @@ pub fn init_logging() -> Result<WorkerGuard, Box<dyn std::error::Error + Send +
     } else {
         fmt::layer().pretty().with_writer(non_blocking_writer).boxed()
     };
-    Registry::default().with(env_filter).with(formatting_layer).try_init()?;
 
-    Ok(guard)
+    let (otlp_layer, otlp_provider) = match config::otlp_endpoint() {
+        Some(endpoint) => {
+            let provider = opentelemetry_otlp::new_pipeline()
+                .tracing()
+                .with_exporter(opentelemetry_otlp::new_exporter().tonic().with_endpoint(endpoint))
+                .install_batch(opentelemetry_sdk::runtime::Tokio)?;
+            let tracer = provider.tracer("simple-chat");
+            (Some(tracing_opentelemetry::layer().with_tracer(tracer)), Some(provider))
+        }
+        None => (None, None),
+    };
+
+    Registry::default()
+        .with(env_filter)
+        .with(formatting_layer)
+        .with(otlp_layer)
+        .try_init()?;
+
+    Ok(TelemetryGuard { _log_guard: guard, otlp_provider })
 }
@@ -8,19 +8,27 @@ use std::{
 };
 
 use common::security;
+use tokio::sync::watch;
 use tracing::{info, warn};
 
 use crate::chat::{
     message,
+    metrics::{Metrics, get_metrics, spawn_metrics_server},
+    rate_limiter,
     room::{Error as RoomError, MessageQueue, RecvError, get_room},
     user::{UserRegistry, get_registry},
 };
 
 const DEFAULT_SEND_TIMEOUT: Duration = Duration::from_millis(100);
 const DEFAULT_RECV_TIMEOUT: Duration = Duration::from_millis(100);
+/// How many `DEFAULT_RECV_TIMEOUT` idle ticks the dispatcher waits between
+/// `retain_recent()` shrink passes over the keyed message rate limiter, so
+/// disconnected users' buckets don't accumulate in its `DashMap` forever.
+const RATE_LIMITER_SHRINK_INTERVAL_TICKS: u32 = 600;
 static BROKER: LazyLock<MessageBroker> = LazyLock::new(|| {
     let broker = MessageBroker::new();
     broker.start_dispatcher();
+    spawn_metrics_server(broker.metrics());
     broker
 });
 
@@ -28,36 +36,95 @@ pub fn get_broker() -> &'static MessageBroker {
     &BROKER
 }
 
+/// Dispatches server-wide presence notices (`JOINED`/`LEFT`) to every
+/// connected user via a single global [`MessageQueue`] and background
+/// dispatcher thread.
+///
+/// This is deliberately a *single* queue rather than a `room name ->
+/// MessageQueue` map: per-room routing (a lazily-created queue per room,
+/// scoped broadcast to just that room's members) already exists in
+/// [`room_registry::RoomRegistry`](crate::chat::room_registry::RoomRegistry),
+/// which `connection::handle_joined_session`'s `SEND`/`JOINROOM`/`PART`
+/// handling calls directly for room chat traffic. Building a second,
+/// competing per-room queue here would duplicate that registry for no
+/// benefit, so `MessageBroker` is scoped down to what it's actually used
+/// for: the one cross-room feed of join/leave notices every client sees
+/// regardless of which rooms it's in.
 pub struct MessageBroker {
     room: &'static dyn MessageQueue,
     registry: &'static UserRegistry,
+    metrics: &'static Metrics,
     dispatcher_handle: Mutex<Option<JoinHandle<()>>>,
     shutdown_flag: Arc<AtomicBool>,
+    shutdown_tx: watch::Sender<bool>,
 }
 
 impl MessageBroker {
     fn new() -> Self {
+        let (shutdown_tx, _) = watch::channel(false);
         Self {
             room: get_room(),
             registry: get_registry(),
+            metrics: get_metrics(),
             dispatcher_handle: Mutex::new(None),
             shutdown_flag: Arc::new(AtomicBool::new(false)),
+            shutdown_tx,
         }
     }
 
     pub const fn registry(&self) -> &UserRegistry {
         self.registry
     }
-    // our use case is broadcase to all
+
+    pub const fn metrics(&self) -> &Metrics {
+        self.metrics
+    }
+
+    /// Forwards `msg` to the single global notice queue, fanned out to
+    /// every connected user regardless of room membership. Named
+    /// `forward_to_room` for historical reasons, but "room" here means the
+    /// whole server's presence feed, not a [`room_registry`](crate::chat::room_registry)
+    /// room — use `room_registry::RoomRegistry::broadcast` for scoped
+    /// per-room delivery.
     pub fn forward_to_room(&self, msg: String) -> Result<(), RoomError> {
-        self.room.send_timeout(msg, DEFAULT_SEND_TIMEOUT)
+        let result = self.room.send_timeout(msg, DEFAULT_SEND_TIMEOUT);
+        if result.is_ok() {
+            self.metrics.messages_forwarded.inc();
+        }
+        result
+    }
+
+    /// Subscribes to the shutdown watch channel; each connection's session
+    /// loop holds one of these and exits gracefully once it flips to `true`.
+    pub fn shutdown_rx(&self) -> watch::Receiver<bool> {
+        self.shutdown_tx.subscribe()
+    }
+
+    /// Drains the server: flags the dispatcher to stop, closes the room so
+    /// its blocking receiver unblocks with `RecvError::Disconnected`, joins
+    /// the dispatcher thread, then signals every subscribed session via the
+    /// watch channel so they can notify their client and clean up.
+    pub fn shutdown(&self) {
+        self.shutdown_flag.store(true, Ordering::Relaxed);
+        self.room.close();
+
+        if let Ok(mut guard) = self.dispatcher_handle.lock() {
+            if let Some(handle) = guard.take() {
+                let _ = handle.join();
+            }
+        }
+
+        let _ = self.shutdown_tx.send(true);
     }
     fn start_dispatcher(&self) {
         let receiver = self.room.receiver();
         let registry = self.registry;
+        let metrics = self.metrics;
         let shutdown_flag = Arc::clone(&self.shutdown_flag);
 
         let handle = thread::spawn(move || {
+            let mut idle_ticks: u32 = 0;
+
             loop {
                 if shutdown_flag.load(Ordering::Relaxed) {
                     info!("Dispatcher received shutdown signal");
@@ -66,18 +133,28 @@ impl MessageBroker {
 
                 match receiver.recv_timeout(DEFAULT_RECV_TIMEOUT) {
                     Ok(serialized) => {
-                        if let Some(msg) = message::ChatMessage::deserialize(&serialized) {
-                            let sent = registry.broadcast(&msg.1, Some(&msg.0)).unwrap_or(0);
+                        idle_ticks = 0;
+                        if let Some((sender, _ts, content)) = message::ChatMessage::deserialize(&serialized) {
+                            let sent = registry.broadcast_blocking(&content, Some(&sender)).unwrap_or(0);
+                            metrics.messages_dispatched.inc();
+                            metrics.fanout_total.inc_by(sent as u64);
                             if sent > 0 {
-                                let safe_username = security::sanitize_for_log(&msg.0.to_string());
+                                let safe_username = security::sanitize_for_log(&sender.to_string());
                                 info!("Dispatched message from '{}' to {} users", safe_username, sent);
                             }
                         } else {
+                            metrics.deserialize_failures.inc();
                             let safe_msg = security::truncate_for_log(&security::sanitize_for_log(&serialized), 100);
                             warn!("Failed to deserialize message: {safe_msg}");
                         }
                     }
-                    Err(RecvError::Timeout) => {}
+                    Err(RecvError::Timeout) => {
+                        idle_ticks = idle_ticks.saturating_add(1);
+                        if idle_ticks >= RATE_LIMITER_SHRINK_INTERVAL_TICKS {
+                            idle_ticks = 0;
+                            rate_limiter::get_message_rate_limiter().retain_recent();
+                        }
+                    }
                     Err(RecvError::Disconnected) => {
                         info!("Room channel disconnected, stopping dispatcher");
                         break;
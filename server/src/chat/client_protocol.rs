@@ -12,6 +12,11 @@ pub enum ParseError {
     UnknownCommand(String),
     MissingUsername,
     MissingMessage,
+    MissingRoom,
+    InvalidHistoryLimit(String),
+    InvalidHistoryTimestamp(String),
+    MissingCredentials,
+    MissingTarget,
 }
 
 impl std::error::Error for ParseError {}
@@ -27,6 +32,26 @@ impl Display for ParseError {
             Self::MissingMessage => {
                 write!(f, "missing message for {} command", consts::CLIENT_SEND_CMD)
             }
+            Self::MissingRoom => {
+                write!(
+                    f,
+                    "missing room name for {}/{} command",
+                    consts::CLIENT_JOIN_ROOM_CMD,
+                    consts::CLIENT_PART_ROOM_CMD
+                )
+            }
+            Self::InvalidHistoryLimit(value) => {
+                write!(f, "invalid {} limit: '{value}'", consts::CLIENT_HISTORY_CMD)
+            }
+            Self::InvalidHistoryTimestamp(value) => {
+                write!(f, "invalid {} AFTER timestamp: '{value}'", consts::CLIENT_HISTORY_CMD)
+            }
+            Self::MissingCredentials => {
+                write!(f, "usage: {}<mechanism> <payload>", consts::CLIENT_AUTH_PREFIX)
+            }
+            Self::MissingTarget => {
+                write!(f, "usage: {}<username> <message>", consts::CLIENT_MSG_PREFIX)
+            }
         }
     }
 }
@@ -38,6 +63,25 @@ pub enum ClientCommand {
     Send { message: String },
 
     Leave,
+
+    JoinRoom { room: String },
+
+    PartRoom { room: String },
+
+    History { limit: Option<usize> },
+
+    /// `HISTORY AFTER <rfc3339-timestamp>`: every retained message strictly
+    /// newer than `timestamp`. Kept as a raw string here and parsed against
+    /// the room's stored timestamps by the caller, same as `Msg`'s body.
+    HistoryAfter { timestamp: String },
+
+    /// SASL-style auth: `mechanism` names the scheme (e.g. `PLAIN`) and
+    /// `payload` is the scheme-specific, typically base64-encoded, blob.
+    Auth { mechanism: String, payload: String },
+
+    Msg { target: String, message: String },
+
+    Who,
 }
 
 impl FromStr for ClientCommand {
@@ -73,6 +117,75 @@ impl FromStr for ClientCommand {
                 Ok(Self::Send { message })
             }
             consts::CLIENT_LEAVE_CMD => Ok(Self::Leave),
+            consts::CLIENT_JOIN_ROOM_CMD => {
+                let room = rest.ok_or(ParseError::MissingRoom)?.to_string();
+                if room.is_empty() {
+                    return Err(ParseError::MissingRoom);
+                }
+                Ok(Self::JoinRoom { room })
+            }
+            consts::CLIENT_PART_ROOM_CMD => {
+                let room = rest.ok_or(ParseError::MissingRoom)?.to_string();
+                if room.is_empty() {
+                    return Err(ParseError::MissingRoom);
+                }
+                Ok(Self::PartRoom { room })
+            }
+            consts::CLIENT_HISTORY_CMD => match rest {
+                None => Ok(Self::History { limit: None }),
+                Some(value) => {
+                    let (keyword, arg) = match sz::find(value, " ") {
+                        Some(idx) => (&value[..idx], value[idx.saturating_add(1)..].trim()),
+                        None => (value, ""),
+                    };
+                    match keyword.to_uppercase().as_str() {
+                        consts::CLIENT_HISTORY_AFTER_KEYWORD => {
+                            if arg.is_empty() {
+                                return Err(ParseError::InvalidHistoryTimestamp(arg.to_string()));
+                            }
+                            Ok(Self::HistoryAfter {
+                                timestamp: arg.to_string(),
+                            })
+                        }
+                        consts::CLIENT_HISTORY_LATEST_KEYWORD => {
+                            let limit = arg
+                                .parse::<usize>()
+                                .map_err(|_| ParseError::InvalidHistoryLimit(arg.to_string()))?;
+                            Ok(Self::History { limit: Some(limit) })
+                        }
+                        // Bare `HISTORY <n>`, kept for backward compatibility.
+                        _ => {
+                            let limit = value
+                                .parse::<usize>()
+                                .map_err(|_| ParseError::InvalidHistoryLimit(value.to_string()))?;
+                            Ok(Self::History { limit: Some(limit) })
+                        }
+                    }
+                }
+            },
+            consts::CLIENT_AUTH_CMD => {
+                let rest = rest.ok_or(ParseError::MissingCredentials)?;
+                let (mechanism, payload) = rest.split_once(' ').ok_or(ParseError::MissingCredentials)?;
+                if mechanism.is_empty() || payload.is_empty() {
+                    return Err(ParseError::MissingCredentials);
+                }
+                Ok(Self::Auth {
+                    mechanism: mechanism.to_string(),
+                    payload: payload.to_string(),
+                })
+            }
+            consts::CLIENT_MSG_CMD => {
+                let rest = rest.ok_or(ParseError::MissingTarget)?;
+                let (target, message) = rest.split_once(' ').ok_or(ParseError::MissingTarget)?;
+                if target.is_empty() || message.is_empty() {
+                    return Err(ParseError::MissingTarget);
+                }
+                Ok(Self::Msg {
+                    target: target.to_string(),
+                    message: message.to_string(),
+                })
+            }
+            consts::CLIENT_WHO_CMD => Ok(Self::Who),
             _ => Err(ParseError::UnknownCommand(command.to_string())),
         }
     }
@@ -84,6 +197,22 @@ impl Display for ClientCommand {
             Self::Join { username } => write!(f, "{}{username}", consts::CLIENT_JOIN_PREFIX),
             Self::Send { message } => write!(f, "{}{message}", consts::CLIENT_SEND_PREFIX),
             Self::Leave => write!(f, "{}", consts::CLIENT_LEAVE_PREFIX),
+            Self::JoinRoom { room } => write!(f, "{}{room}", consts::CLIENT_JOIN_ROOM_PREFIX),
+            Self::PartRoom { room } => write!(f, "{}{room}", consts::CLIENT_PART_ROOM_PREFIX),
+            Self::History { limit: None } => write!(f, "{}", consts::CLIENT_HISTORY_PREFIX),
+            Self::History { limit: Some(limit) } => {
+                write!(f, "{} {limit}", consts::CLIENT_HISTORY_PREFIX)
+            }
+            Self::HistoryAfter { timestamp } => {
+                write!(f, "{} AFTER {timestamp}", consts::CLIENT_HISTORY_PREFIX)
+            }
+            Self::Auth { mechanism, payload } => {
+                write!(f, "{}{mechanism} {payload}", consts::CLIENT_AUTH_PREFIX)
+            }
+            Self::Msg { target, message } => {
+                write!(f, "{}{target} {message}", consts::CLIENT_MSG_PREFIX)
+            }
+            Self::Who => write!(f, "{}", consts::CLIENT_WHO_PREFIX),
         }
     }
 }
@@ -170,6 +299,221 @@ mod tests {
         assert_eq!(lower, ClientCommand::Leave);
     }
 
+    #[test]
+    fn test_client_join_room_command_format() {
+        let input = format!("{}{}", consts::CLIENT_JOIN_ROOM_PREFIX, "general");
+        let parsed: ClientCommand = input.parse().expect("should parse JOINROOM command");
+        assert_eq!(
+            parsed,
+            ClientCommand::JoinRoom {
+                room: "general".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_part_room_command_format() {
+        let input = format!("{}{}", consts::CLIENT_PART_ROOM_PREFIX, "general");
+        let parsed: ClientCommand = input.parse().expect("should parse PART command");
+        assert_eq!(
+            parsed,
+            ClientCommand::PartRoom {
+                room: "general".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_join_room_missing_room() {
+        let err = "JOINROOM".parse::<ClientCommand>().unwrap_err();
+        assert!(matches!(err, ParseError::MissingRoom));
+    }
+
+    #[test]
+    fn test_display_roundtrip_join_room() {
+        let original = ClientCommand::JoinRoom {
+            room: "lobby".to_string(),
+        };
+        let formatted = original.to_string();
+        let parsed: ClientCommand = formatted.parse().expect("roundtrip should work");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_display_roundtrip_part_room() {
+        let original = ClientCommand::PartRoom {
+            room: "lobby".to_string(),
+        };
+        let formatted = original.to_string();
+        let parsed: ClientCommand = formatted.parse().expect("roundtrip should work");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_client_history_no_limit() {
+        let parsed: ClientCommand = "HISTORY".parse().expect("should parse HISTORY with no limit");
+        assert_eq!(parsed, ClientCommand::History { limit: None });
+    }
+
+    #[test]
+    fn test_client_history_with_limit() {
+        let parsed: ClientCommand = "HISTORY 10".parse().expect("should parse HISTORY with limit");
+        assert_eq!(parsed, ClientCommand::History { limit: Some(10) });
+    }
+
+    #[test]
+    fn test_client_history_invalid_limit() {
+        let err = "HISTORY nope".parse::<ClientCommand>().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidHistoryLimit(v) if v == "nope"));
+    }
+
+    #[test]
+    fn test_display_roundtrip_history() {
+        let original = ClientCommand::History { limit: Some(42) };
+        let formatted = original.to_string();
+        let parsed: ClientCommand = formatted.parse().expect("roundtrip should work");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_client_history_latest_keyword() {
+        let parsed: ClientCommand = "HISTORY LATEST 10".parse().expect("should parse HISTORY LATEST");
+        assert_eq!(parsed, ClientCommand::History { limit: Some(10) });
+    }
+
+    #[test]
+    fn test_client_history_after_keyword() {
+        let parsed: ClientCommand = "HISTORY AFTER 2024-01-01T00:00:00Z"
+            .parse()
+            .expect("should parse HISTORY AFTER");
+        assert_eq!(
+            parsed,
+            ClientCommand::HistoryAfter {
+                timestamp: "2024-01-01T00:00:00Z".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_history_after_case_insensitive() {
+        let parsed: ClientCommand = "HISTORY after 2024-01-01T00:00:00Z"
+            .parse()
+            .expect("should parse lowercase AFTER");
+        assert_eq!(
+            parsed,
+            ClientCommand::HistoryAfter {
+                timestamp: "2024-01-01T00:00:00Z".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_history_after_missing_timestamp() {
+        let err = "HISTORY AFTER".parse::<ClientCommand>().unwrap_err();
+        assert!(matches!(err, ParseError::InvalidHistoryTimestamp(_)));
+    }
+
+    #[test]
+    fn test_display_roundtrip_history_after() {
+        let original = ClientCommand::HistoryAfter {
+            timestamp: "2024-01-01T00:00:00Z".to_string(),
+        };
+        let formatted = original.to_string();
+        let parsed: ClientCommand = formatted.parse().expect("roundtrip should work");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_client_auth_command_format() {
+        let parsed: ClientCommand = "AUTH PLAIN YWxpY2UAYWxpY2UAaHVudGVyMg=="
+            .parse()
+            .expect("should parse AUTH command");
+        assert_eq!(
+            parsed,
+            ClientCommand::Auth {
+                mechanism: "PLAIN".to_string(),
+                payload: "YWxpY2UAYWxpY2UAaHVudGVyMg==".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_auth_missing_payload() {
+        let err = "AUTH PLAIN".parse::<ClientCommand>().unwrap_err();
+        assert!(matches!(err, ParseError::MissingCredentials));
+    }
+
+    #[test]
+    fn test_client_auth_missing_credentials() {
+        let err = "AUTH".parse::<ClientCommand>().unwrap_err();
+        assert!(matches!(err, ParseError::MissingCredentials));
+    }
+
+    #[test]
+    fn test_display_roundtrip_auth() {
+        let original = ClientCommand::Auth {
+            mechanism: "PLAIN".to_string(),
+            payload: "Ym9iAGJvYgBzM2NyZXQ=".to_string(),
+        };
+        let formatted = original.to_string();
+        let parsed: ClientCommand = formatted.parse().expect("roundtrip should work");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_client_msg_command_format() {
+        let parsed: ClientCommand = "MSG bob hey there".parse().expect("should parse MSG command");
+        assert_eq!(
+            parsed,
+            ClientCommand::Msg {
+                target: "bob".to_string(),
+                message: "hey there".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_client_msg_missing_message() {
+        let err = "MSG bob".parse::<ClientCommand>().unwrap_err();
+        assert!(matches!(err, ParseError::MissingTarget));
+    }
+
+    #[test]
+    fn test_client_msg_missing_target() {
+        let err = "MSG".parse::<ClientCommand>().unwrap_err();
+        assert!(matches!(err, ParseError::MissingTarget));
+    }
+
+    #[test]
+    fn test_display_roundtrip_msg() {
+        let original = ClientCommand::Msg {
+            target: "bob".to_string(),
+            message: "hey there".to_string(),
+        };
+        let formatted = original.to_string();
+        let parsed: ClientCommand = formatted.parse().expect("roundtrip should work");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_client_who_command_format() {
+        let parsed: ClientCommand = "WHO".parse().expect("should parse WHO command");
+        assert_eq!(parsed, ClientCommand::Who);
+    }
+
+    #[test]
+    fn test_case_insensitive_who() {
+        let lower: ClientCommand = "who".parse().expect("lowercase should work");
+        assert_eq!(lower, ClientCommand::Who);
+    }
+
+    #[test]
+    fn test_display_roundtrip_who() {
+        let formatted = ClientCommand::Who.to_string();
+        let parsed: ClientCommand = formatted.parse().expect("roundtrip should work");
+        assert_eq!(parsed, ClientCommand::Who);
+    }
+
     #[test]
     fn test_whitespace_trimming() {
         let with_newline: ClientCommand = "JOIN alice\n".parse().expect("should handle newline");
@@ -1,26 +1,38 @@
-use std::net::SocketAddr;
+use std::{collections::HashSet, net::SocketAddr};
 
-use common::security::{self, MAX_LINE_LENGTH, READ_TIMEOUT};
-use crossbeam::channel::{Receiver, Sender, bounded};
+use common::{
+    io_limits::{LimitedLineReader, LineReadError},
+    security::{self, AUTH_ATTEMPT_BURST_CAPACITY, MAX_AUTH_ATTEMPTS_PER_SECOND, MAX_LINE_LENGTH},
+};
+use parking_lot::RwLock;
 use thiserror::Error;
 use tokio::{
-    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    net::{TcpStream, tcp::OwnedWriteHalf},
-    time::timeout,
+    io::{AsyncRead, AsyncWrite, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::mpsc::{self, Receiver, Sender},
 };
 use tracing::{error, info, warn};
 
 use crate::chat::{
     broker::get_broker,
     client_protocol::ClientCommand,
+    credentials,
     message::ChatMessage,
-    rate_limiter::RateLimiter,
+    rate_limiter::{self, RateLimiter},
+    room::OneToMany,
+    room_registry::{self, HistoryQuery, RoomName},
     server_protocol::ServerMessage,
     user::{User, Username},
 };
 
 const USER_CHANNEL_BUFFER_SIZE: usize = 256;
 
+/// Either transport's read half, boxed so the session handling below is
+/// written once and works for a plain TCP connection or a QUIC stream.
+pub type BoxedReader = Box<dyn AsyncRead + Unpin + Send>;
+/// Either transport's write half, boxed for the same reason.
+pub type BoxedWriter = Box<dyn AsyncWrite + Unpin + Send>;
+
 #[derive(Debug, Error)]
 pub enum ConnectionError {
     #[error("IO error: {0}")]
@@ -35,22 +47,111 @@ pub enum ConnectionError {
 
 struct Unauthenticated {
     addr: SocketAddr,
-    tx: Sender<String>,
-    rx: Receiver<String>,
+    tx: Sender<OneToMany>,
+    rx: Receiver<OneToMany>,
+
+    /// Username verified by a prior successful `AUTH`, if any.
+    authenticated_as: Option<Username>,
+    auth_limiter: RateLimiter,
 }
 
 struct Joined {
     user: User,
     addr: SocketAddr,
-    rx: Receiver<String>,
+    rx: Receiver<OneToMany>,
 
     rate_limiter: RateLimiter,
+    /// Rooms this connection currently has membership in, via `room_registry`.
+    rooms: RwLock<HashSet<RoomName>>,
+    /// Reaps the registry entry if this session ends without reaching `cleanup`.
+    guard: ConnectionGuard,
+}
+
+/// Mirrors the "dead client" pattern used by the NATS example's `ClientInner`:
+/// on drop (disconnect, timeout, or panic) it unregisters the user and
+/// broadcasts a `LEFT` notice, so a connection that vanishes without sending
+/// `LEAVE` doesn't leak a registry entry or leave its username stuck "taken".
+/// `Joined::cleanup` disarms this guard first, since it already performs the
+/// same unregister-and-notify sequence (plus per-room parting) on its own.
+struct ConnectionGuard {
+    user: User,
+    disarmed: bool,
+}
+
+impl ConnectionGuard {
+    const fn new(user: User) -> Self {
+        Self { user, disarmed: false }
+    }
+
+    fn disarm(&mut self) {
+        self.disarmed = true;
+    }
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        if self.disarmed {
+            return;
+        }
+
+        let broker = get_broker();
+        match broker.registry().unregister(&self.user) {
+            Ok(true) => {
+                broker.metrics().connected_users.dec();
+
+                let leave_msg = ServerMessage::UserLeft {
+                    username: self.user.get_username().to_string(),
+                };
+                let msg = ChatMessage::new(&self.user, leave_msg.to_string()).serialize();
+                if let Err(e) = broker.forward_to_room(msg) {
+                    warn!("Failed to send message to room: {e}");
+                }
+
+                let safe_username = security::sanitize_for_log(&self.user.get_username().to_string());
+                info!("User '{}' reaped after abrupt disconnect", safe_username);
+            }
+            Ok(false) => {}
+            Err(e) => warn!("Failed to unregister user on drop: {e}"),
+        }
+    }
 }
 
 impl Unauthenticated {
     fn new(addr: SocketAddr) -> Self {
-        let (tx, rx) = bounded(USER_CHANNEL_BUFFER_SIZE);
-        Self { addr, tx, rx }
+        let (tx, rx) = mpsc::channel(USER_CHANNEL_BUFFER_SIZE);
+        Self {
+            addr,
+            tx,
+            rx,
+            authenticated_as: None,
+            auth_limiter: RateLimiter::with_config(MAX_AUTH_ATTEMPTS_PER_SECOND, AUTH_ATTEMPT_BURST_CAPACITY),
+        }
+    }
+
+    /// Runs a SASL-style `AUTH <mechanism> <payload>` exchange. Only `PLAIN`
+    /// is supported: `payload` is base64 of `authzid \0 authcid \0 password`.
+    /// On success, remembers the authenticated username so a following
+    /// `JOIN` for it is admitted. Rate-limited per connection to resist
+    /// password guessing.
+    async fn try_auth(&mut self, mechanism: String, payload: String) -> Result<(), String> {
+        if !self.auth_limiter.try_acquire() {
+            get_broker().metrics().rate_limit_rejections.inc();
+            return Err("too many AUTH attempts, slow down".to_string());
+        }
+
+        if !mechanism.eq_ignore_ascii_case(common::consts::CLIENT_AUTH_MECHANISM_PLAIN) {
+            return Err(credentials::Error::UnsupportedMechanism(mechanism).to_string());
+        }
+
+        let (raw_username, password) = credentials::decode_plain(&payload).map_err(|e| e.to_string())?;
+        let username = Username::new(raw_username).map_err(|e| e.to_string())?;
+        credentials::get_store()
+            .verify(&username, &password)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        self.authenticated_as = Some(username);
+        Ok(())
     }
 
     fn try_join(self, raw_username: String) -> Result<Joined, (Self, String)> {
@@ -61,9 +162,12 @@ impl Unauthenticated {
 
         let broker = get_broker();
         let registry = broker.registry();
+        let authenticated = self.authenticated_as.as_ref() == Some(&username);
 
-        match registry.register(&username, self.tx.clone()) {
+        match registry.register(&username, self.tx.clone(), authenticated) {
             Ok(registered_user) => {
+                broker.metrics().connected_users.inc();
+
                 let join_msg = ServerMessage::UserJoined {
                     username: registered_user.get_username().to_string(),
                 };
@@ -77,10 +181,12 @@ impl Unauthenticated {
                 info!("User '{}' joined from {}", safe_username, self.addr);
 
                 Ok(Joined {
+                    guard: ConnectionGuard::new(registered_user.clone()),
                     user: registered_user,
                     addr: self.addr,
                     rx: self.rx,
                     rate_limiter: RateLimiter::new(),
+                    rooms: RwLock::new(HashSet::new()),
                 })
             }
             Err(e) => Err((self, e.to_string())),
@@ -89,7 +195,11 @@ impl Unauthenticated {
 }
 
 impl Joined {
-    async fn drain_broadcasts(&self, writer: &mut OwnedWriteHalf) -> Result<(), ConnectionError> {
+    /// Best-effort flush of whatever's already buffered in the delivery
+    /// channel, without waiting for more to arrive. Used right before the
+    /// connection closes, when a final awaited `recv()` would block forever
+    /// once the sender side has nothing left to say.
+    async fn drain_broadcasts(&mut self, writer: &mut BoxedWriter) -> Result<(), ConnectionError> {
         while let Ok(msg) = self.rx.try_recv() {
             writer.write_all(msg.as_bytes()).await?;
             writer.write_all(b"\n").await?;
@@ -98,12 +208,29 @@ impl Joined {
         Ok(())
     }
 
-    fn cleanup(self) {
+    async fn cleanup(mut self) {
+        self.guard.disarm();
+
         let username = self.user.get_username();
         let broker = get_broker();
         let registry = broker.registry();
 
-        let _ = registry.unregister(&self.user);
+        if registry.unregister(&self.user).unwrap_or(false) {
+            broker.metrics().connected_users.dec();
+        }
+
+        let room_registry = room_registry::get_registry();
+        let rooms: Vec<RoomName> = self.rooms.read().iter().cloned().collect();
+        for room in rooms {
+            if room_registry.part(&room, &username).unwrap_or(false) {
+                let notice = ServerMessage::UserLeft {
+                    username: username.to_string(),
+                };
+                if let Err(e) = room_registry.broadcast(&room, &notice.to_string(), Some(&username)).await {
+                    warn!("Failed to broadcast room part for '{room}': {e}");
+                }
+            }
+        }
 
         let leave_msg = ServerMessage::UserLeft {
             username: username.to_string(),
@@ -118,17 +245,26 @@ impl Joined {
     }
 }
 
-pub async fn handle_connection(stream: TcpStream, addr: SocketAddr) {
+/// Handles a plain TCP connection: splits it into boxed halves and dispatches
+/// to the transport-agnostic [`handle_connection`].
+pub async fn handle_tcp_connection(stream: TcpStream, addr: SocketAddr) {
+    let (reader, writer) = stream.into_split();
+    handle_connection(Box::new(reader), Box::new(writer), addr).await;
+}
+
+/// Runs the full connection lifecycle (auth/join handshake, then the joined
+/// session loop) over any transport, as long as its halves are boxed as
+/// [`BoxedReader`]/[`BoxedWriter`]. Used for both the TCP and QUIC listeners.
+pub async fn handle_connection(reader: BoxedReader, writer: BoxedWriter, addr: SocketAddr) {
     info!("New connection from {addr}");
 
-    if let Err(e) = handle_connection_inner(stream, addr).await {
+    if let Err(e) = handle_connection_inner(reader, writer, addr).await {
         error!("Connection {addr} error: {e}");
     }
 }
 
-async fn handle_connection_inner(stream: TcpStream, addr: SocketAddr) -> Result<(), ConnectionError> {
-    let (reader, mut writer) = stream.into_split();
-    let mut reader = BufReader::new(reader);
+async fn handle_connection_inner(reader: BoxedReader, mut writer: BoxedWriter, addr: SocketAddr) -> Result<(), ConnectionError> {
+    let mut reader = LimitedLineReader::new(BufReader::new(reader));
     let mut line = String::new();
 
     let state = Unauthenticated::new(addr);
@@ -144,36 +280,40 @@ async fn handle_connection_inner(stream: TcpStream, addr: SocketAddr) -> Result<
 
 async fn wait_for_join(
     mut state: Unauthenticated,
-    reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
-    writer: &mut OwnedWriteHalf,
+    reader: &mut LimitedLineReader<BufReader<BoxedReader>>,
+    writer: &mut BoxedWriter,
     line: &mut String,
 ) -> Result<Option<Joined>, ConnectionError> {
     loop {
         line.clear();
 
-        let read_result = timeout(READ_TIMEOUT, reader.read_line(line)).await;
-
-        let bytes_read = match read_result {
-            Ok(Ok(n)) => n,
-            Ok(Err(e)) => return Err(ConnectionError::Io(e)),
-            Err(_) => {
+        let bytes_read = match reader.read_line(line).await {
+            Ok(n) => n,
+            Err(LineReadError::Timeout(_)) => {
                 warn!("Connection {} timed out during join", state.addr);
                 return Err(ConnectionError::Timeout);
             }
+            Err(LineReadError::LineTooLong(_)) => {
+                get_broker().metrics().oversized_drops.inc();
+                warn!("Connection {} sent oversized message during join", state.addr);
+                send_message_to_client(
+                    writer,
+                    &ServerMessage::Error {
+                        reason: "message too long".to_string(),
+                    },
+                )
+                .await?;
+                return Err(ConnectionError::MessageTooLong);
+            }
+            Err(LineReadError::InvalidUtf8) => {
+                return Err(ConnectionError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "line was not valid UTF-8",
+                )));
+            }
+            Err(LineReadError::Io(e)) => return Err(ConnectionError::Io(e)),
         };
 
-        if bytes_read > MAX_LINE_LENGTH {
-            warn!("Connection {} sent oversized message during join", state.addr);
-            send_message_to_client(
-                writer,
-                &ServerMessage::Error {
-                    reason: "message too long".to_string(),
-                },
-            )
-            .await?;
-            return Err(ConnectionError::MessageTooLong);
-        }
-
         if bytes_read == 0 {
             info!("Connection {} closed before joining", state.addr);
             return Ok(None);
@@ -191,6 +331,11 @@ async fn wait_for_join(
                 }
             },
 
+            Ok(ClientCommand::Auth { mechanism, payload }) => match state.try_auth(mechanism, payload).await {
+                Ok(()) => send_message_to_client(writer, &ServerMessage::Ok).await?,
+                Err(reason) => send_message_to_client(writer, &ServerMessage::AuthFailed { reason }).await?,
+            },
+
             Ok(_) => {
                 send_message_to_client(
                     writer,
@@ -209,62 +354,303 @@ async fn wait_for_join(
 }
 
 async fn handle_joined_session(
-    joined: Joined,
-    reader: &mut BufReader<tokio::net::tcp::OwnedReadHalf>,
-    writer: &mut OwnedWriteHalf,
+    mut joined: Joined,
+    reader: &mut LimitedLineReader<BufReader<BoxedReader>>,
+    writer: &mut BoxedWriter,
     line: &mut String,
 ) -> Result<(), ConnectionError> {
     let broker = get_broker();
+    let mut shutdown_rx = broker.shutdown_rx();
     loop {
         line.clear();
 
         tokio::select! {
 
-            read_result = timeout(READ_TIMEOUT, reader.read_line(line)) => {
-                let bytes_read = match read_result {
-                    Ok(Ok(n)) => n,
-                    Ok(Err(e)) => return Err(ConnectionError::Io(e)),
-                    Err(_) => {
+            changed = shutdown_rx.changed() => {
+                if changed.is_err() || !*shutdown_rx.borrow() {
+                    continue;
+                }
+                joined.drain_broadcasts(writer).await?;
+                let notice = ServerMessage::ServerShutdown {
+                    reason: "server is shutting down".to_string(),
+                };
+                send_message_to_client(writer, &notice).await?;
+                break;
+            }
 
+            read_result = reader.read_line(line) => {
+                let bytes_read = match read_result {
+                    Ok(n) => n,
+                    Err(LineReadError::Timeout(_)) => {
                         continue;
                     }
+                    Err(LineReadError::LineTooLong(_)) => {
+                        broker.metrics().oversized_drops.inc();
+                        let safe_username = security::sanitize_for_log(&joined.user.get_username().to_string());
+                        warn!("User '{}' sent oversized message, dropping connection", safe_username);
+                        send_message_to_client(writer, &ServerMessage::Error {
+                            reason: "message too long".to_string(),
+                        }).await?;
+                        // The over-limit line's remaining bytes were left unread in the
+                        // socket buffer (see LimitedLineReader::read_line), so the framing
+                        // can no longer be trusted; end the session instead of continuing.
+                        return Err(ConnectionError::MessageTooLong);
+                    }
+                    Err(LineReadError::InvalidUtf8) => {
+                        return Err(ConnectionError::Io(std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            "line was not valid UTF-8",
+                        )));
+                    }
+                    Err(LineReadError::Io(e)) => return Err(ConnectionError::Io(e)),
                 };
 
-
-                if bytes_read > MAX_LINE_LENGTH {
-                    let safe_username = security::sanitize_for_log(&joined.user.get_username().to_string());
-                    warn!("User '{}' sent oversized message", safe_username);
-                    send_message_to_client(writer, &ServerMessage::Error {
-                        reason: "message too long".to_string(),
-                    }).await?;
-                    continue;
-                }
-
                 if bytes_read == 0 {
                     info!("Connection {} closed by client", joined.addr);
                     break;
                 }
 
                 match line.trim().parse::<ClientCommand>() {
-                    Ok(ClientCommand::Join { .. }) => {
+                    Ok(ClientCommand::Join { .. } | ClientCommand::Auth { .. }) => {
                         send_message_to_client(writer, &ServerMessage::Error {
                             reason: "already joined".to_string(),
                         }).await?;
                     }
 
                     Ok(ClientCommand::Send { message }) => {
-                        joined.rate_limiter.acquire().await;
+                        // Non-blocking: `.await`ing the throttled case here would park this
+                        // select arm and stop `joined.rx.recv()` from being polled, so a
+                        // user who hits their own send quota would also stop receiving
+                        // inbound broadcasts until their bucket refills.
+                        if !joined.rate_limiter.try_acquire()
+                            || !rate_limiter::get_message_rate_limiter().try_acquire(&joined.user.get_username())
+                        {
+                            send_message_to_client(writer, &ServerMessage::Error {
+                                reason: "rate limit exceeded, try again shortly".to_string(),
+                            }).await?;
+                            continue;
+                        }
 
-                        let broadcast_message = ServerMessage::BroadcastMessage {
-                            text: ChatMessage::new(&joined.user, message).serialize(),
-                        };
+                        let member_rooms: Vec<RoomName> = joined.rooms.read().iter().cloned().collect();
+                        if member_rooms.is_empty() {
+                            send_message_to_client(writer, &ServerMessage::Error {
+                                reason: format!("not in any room, use {}<room> first", common::consts::CLIENT_JOIN_ROOM_PREFIX),
+                            }).await?;
+                            continue;
+                        }
+
+                        let text = ChatMessage::new(&joined.user, message.clone()).serialize();
+
+                        let room_registry = room_registry::get_registry();
+                        for room in &member_rooms {
+                            let payload = ServerMessage::BroadcastMessage {
+                                room: room.to_string(),
+                                text: text.clone(),
+                            }
+                            .to_string();
+                            if let Err(e) = room_registry
+                                .broadcast(room, &payload, Some(&joined.user.get_username()))
+                                .await
+                            {
+                                warn!("Failed to broadcast to room '{room}': {e}");
+                                send_message_to_client(writer, &ServerMessage::Error {
+                                    reason: e.to_string(),
+                                }).await?;
+                                continue;
+                            }
+                            if let Err(e) = room_registry.record_history(room, joined.user.get_username(), message.clone()) {
+                                warn!("Failed to record history for '{room}': {e}");
+                            }
+                        }
+                    }
+
+                    Ok(ClientCommand::JoinRoom { room }) => {
+                        match RoomName::new(room) {
+                            Ok(room_name) => {
+                                let room_registry = room_registry::get_registry();
+                                match room_registry.join(&room_name, &joined.user) {
+                                    Ok(_) => {
+                                        joined.rooms.write().insert(room_name.clone());
+                                        let notice = ServerMessage::UserJoined {
+                                            username: joined.user.get_username().to_string(),
+                                        };
+                                        if let Err(e) = room_registry
+                                            .broadcast(&room_name, &notice.to_string(), Some(&joined.user.get_username()))
+                                            .await
+                                        {
+                                            warn!("Failed to broadcast room join for '{room_name}': {e}");
+                                        }
+
+                                        match room_registry.history(&room_name, Some(room_registry::JOIN_HISTORY_REPLAY_LIMIT)) {
+                                            Ok(entries) => {
+                                                for entry in entries {
+                                                    let ts_millis = entry
+                                                        .ts
+                                                        .duration_since(std::time::UNIX_EPOCH)
+                                                        .map(|d| d.as_millis() as u64)
+                                                        .unwrap_or(0);
+                                                    send_message_to_client(writer, &ServerMessage::History {
+                                                        ts_millis,
+                                                        sender: entry.sender.to_string(),
+                                                        body: entry.body,
+                                                    }).await?;
+                                                }
+                                            }
+                                            Err(e) => {
+                                                warn!("Failed to replay history for '{room_name}': {e}");
+                                            }
+                                        }
+                                        send_message_to_client(writer, &ServerMessage::HistoryEnd).await?;
+
+                                        send_message_to_client(writer, &ServerMessage::Ok).await?;
+                                    }
+                                    Err(e) => {
+                                        send_message_to_client(writer, &ServerMessage::Error { reason: e.to_string() }).await?;
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                send_message_to_client(writer, &ServerMessage::Error { reason: e.to_string() }).await?;
+                            }
+                        }
+                    }
+
+                    Ok(ClientCommand::PartRoom { room }) => {
+                        match RoomName::new(room) {
+                            Ok(room_name) => {
+                                let room_registry = room_registry::get_registry();
+                                let was_member = room_registry
+                                    .part(&room_name, &joined.user.get_username())
+                                    .unwrap_or(false);
+                                if was_member {
+                                    joined.rooms.write().remove(&room_name);
+                                    let notice = ServerMessage::UserLeft {
+                                        username: joined.user.get_username().to_string(),
+                                    };
+                                    if let Err(e) = room_registry
+                                        .broadcast(&room_name, &notice.to_string(), Some(&joined.user.get_username()))
+                                        .await
+                                    {
+                                        warn!("Failed to broadcast room part for '{room_name}': {e}");
+                                    }
+                                }
+                                send_message_to_client(writer, &ServerMessage::Ok).await?;
+                            }
+                            Err(e) => {
+                                send_message_to_client(writer, &ServerMessage::Error { reason: e.to_string() }).await?;
+                            }
+                        }
+                    }
+
+                    Ok(ClientCommand::History { limit }) => {
+                        let limit = limit.filter(|&n| n > 0);
+                        let member_rooms: Vec<RoomName> = joined.rooms.read().iter().cloned().collect();
+                        let room_registry = room_registry::get_registry();
+
+                        for room in &member_rooms {
+                            match room_registry.history(room, limit) {
+                                Ok(entries) => {
+                                    for entry in entries {
+                                        let ts_millis = entry
+                                            .ts
+                                            .duration_since(std::time::UNIX_EPOCH)
+                                            .map(|d| d.as_millis() as u64)
+                                            .unwrap_or(0);
+                                        send_message_to_client(writer, &ServerMessage::History {
+                                            ts_millis,
+                                            sender: entry.sender.to_string(),
+                                            body: entry.body,
+                                        }).await?;
+                                    }
+                                }
+                                Err(e) => {
+                                    warn!("Failed to fetch history for '{room}': {e}");
+                                }
+                            }
+                        }
+                        send_message_to_client(writer, &ServerMessage::HistoryEnd).await?;
+                    }
 
-                        let msg = ChatMessage::new(&joined.user, broadcast_message.to_string()).serialize();
-                        if let Err(e) = broker.forward_to_room(msg) {
-                            warn!("Failed to send message to room: {e}");
+                    Ok(ClientCommand::HistoryAfter { timestamp }) => {
+                        match timestamp.parse::<jiff::Timestamp>() {
+                            Ok(after) => {
+                                let after = std::time::UNIX_EPOCH
+                                    + std::time::Duration::new(after.as_second().max(0) as u64, after.subsec_nanosecond() as u32);
+                                let member_rooms: Vec<RoomName> = joined.rooms.read().iter().cloned().collect();
+                                let room_registry = room_registry::get_registry();
+
+                                for room in &member_rooms {
+                                    match room_registry.history_query(room, HistoryQuery::After(after)) {
+                                        Ok(entries) => {
+                                            for entry in entries {
+                                                let ts_millis = entry
+                                                    .ts
+                                                    .duration_since(std::time::UNIX_EPOCH)
+                                                    .map(|d| d.as_millis() as u64)
+                                                    .unwrap_or(0);
+                                                send_message_to_client(writer, &ServerMessage::History {
+                                                    ts_millis,
+                                                    sender: entry.sender.to_string(),
+                                                    body: entry.body,
+                                                }).await?;
+                                            }
+                                        }
+                                        Err(e) => {
+                                            warn!("Failed to fetch history for '{room}': {e}");
+                                        }
+                                    }
+                                }
+                                send_message_to_client(writer, &ServerMessage::HistoryEnd).await?;
+                            }
+                            Err(_) => {
+                                send_message_to_client(writer, &ServerMessage::Error {
+                                    reason: format!("invalid timestamp: '{timestamp}'"),
+                                }).await?;
+                            }
+                        }
+                    }
+
+                    Ok(ClientCommand::Msg { target, message }) => {
+                        // See the `Send` arm above: non-blocking for the same reason.
+                        if !joined.rate_limiter.try_acquire()
+                            || !rate_limiter::get_message_rate_limiter().try_acquire(&joined.user.get_username())
+                        {
                             send_message_to_client(writer, &ServerMessage::Error {
-                                reason: e.to_string(),
+                                reason: "rate limit exceeded, try again shortly".to_string(),
                             }).await?;
+                            continue;
+                        }
+
+                        match Username::new(target) {
+                            Ok(target_username) => {
+                                let dm = ServerMessage::DirectMessage {
+                                    text: ChatMessage::new(&joined.user, message).serialize(),
+                                };
+                                let registry = broker.registry();
+                                match registry.send_direct(&target_username, &dm.to_string()).await {
+                                    Ok(()) => {
+                                        send_message_to_client(writer, &ServerMessage::Ok).await?;
+                                    }
+                                    Err(e) => {
+                                        send_message_to_client(writer, &ServerMessage::Error { reason: e.to_string() }).await?;
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                send_message_to_client(writer, &ServerMessage::Error { reason: e.to_string() }).await?;
+                            }
+                        }
+                    }
+
+                    Ok(ClientCommand::Who) => {
+                        match broker.registry().list_usernames() {
+                            Ok(usernames) => {
+                                let names = usernames.iter().map(ToString::to_string).collect();
+                                send_message_to_client(writer, &ServerMessage::Users { names }).await?;
+                            }
+                            Err(e) => {
+                                send_message_to_client(writer, &ServerMessage::Error { reason: e.to_string() }).await?;
+                            }
                         }
                     }
 
@@ -284,17 +670,26 @@ async fn handle_joined_session(
             }
 
 
-            () = tokio::task::yield_now() => {
-                joined.drain_broadcasts(writer).await?;
+            msg = joined.rx.recv() => {
+                let Some(msg) = msg else {
+                    // Sender side gone, meaning our registry entry was dropped out
+                    // from under us (e.g. reaped elsewhere): nothing will ever
+                    // arrive again, so end the session rather than spin on `recv()`.
+                    info!("Delivery channel closed for {}", joined.addr);
+                    break;
+                };
+                writer.write_all(msg.as_bytes()).await?;
+                writer.write_all(b"\n").await?;
+                writer.flush().await?;
             }
         }
     }
 
-    joined.cleanup();
+    joined.cleanup().await;
     Ok(())
 }
 
-async fn send_message_to_client(writer: &mut OwnedWriteHalf, msg: &ServerMessage) -> Result<(), std::io::Error> {
+async fn send_message_to_client(writer: &mut BoxedWriter, msg: &ServerMessage) -> Result<(), std::io::Error> {
     writer.write_all(msg.to_string().as_bytes()).await?;
     writer.write_all(b"\n").await?;
     writer.flush().await
@@ -0,0 +1,212 @@
+use std::{collections::HashMap, env, fs, sync::LazyLock};
+
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use base64::{Engine as _, engine::general_purpose::STANDARD as BASE64};
+use common::config;
+use stringzilla::sz;
+use thiserror::Error as this_error;
+
+use super::string as my_string;
+use crate::chat::user::Username;
+
+static STORE: LazyLock<CredentialStore> = LazyLock::new(CredentialStore::load_from_env);
+
+/// A valid Argon2id PHC hash of an arbitrary, unguessable placeholder
+/// password that no real credential will ever match. Used in place of a
+/// missing stored hash so that `AUTH` for an unknown username runs the same
+/// Argon2 verification as a wrong password for a known one, rather than
+/// returning early — otherwise the early return would be a timing side
+/// channel an attacker could use to enumerate valid usernames.
+const DUMMY_HASH: &str = "$argon2id$v=19$m=4096,t=3,p=1$c29tZXNhbHQ$SqlVijFGiPG+935vDSGEsA";
+
+pub fn get_store() -> &'static CredentialStore {
+    &STORE
+}
+
+#[derive(Debug, Clone, this_error, PartialEq, Eq)]
+pub enum Error {
+    #[error("invalid password")]
+    AuthFailed,
+
+    #[error("stored credential hash is malformed")]
+    MalformedHash,
+
+    #[error("unsupported SASL mechanism: {0}")]
+    UnsupportedMechanism(String),
+
+    #[error("malformed SASL PLAIN payload")]
+    MalformedPlainPayload,
+}
+
+/// Decodes a SASL PLAIN payload (RFC 4616): base64 of
+/// `authzid \0 authcid \0 password`. `authzid` is accepted but ignored, as
+/// this crate has no notion of authorization identities distinct from the
+/// authenticating user.
+pub fn decode_plain(payload: &str) -> Result<(String, String), Error> {
+    let decoded = BASE64.decode(payload.trim()).map_err(|_| Error::MalformedPlainPayload)?;
+    let mut fields = decoded.split(|&b| b == 0);
+    let _authzid = fields.next().ok_or(Error::MalformedPlainPayload)?;
+    let authcid = fields.next().ok_or(Error::MalformedPlainPayload)?;
+    let password = fields.next().ok_or(Error::MalformedPlainPayload)?;
+    if fields.next().is_some() {
+        return Err(Error::MalformedPlainPayload);
+    }
+
+    let authcid = String::from_utf8(authcid.to_vec()).map_err(|_| Error::MalformedPlainPayload)?;
+    let password = String::from_utf8(password.to_vec()).map_err(|_| Error::MalformedPlainPayload)?;
+    if authcid.is_empty() || password.is_empty() {
+        return Err(Error::MalformedPlainPayload);
+    }
+
+    Ok((authcid, password))
+}
+
+/// Optional Argon2 credential store, loaded once from the file named by
+/// `config::AUTH_CREDENTIALS_PATH_ENV`. Usernames with no entry here keep
+/// the existing open-registration behavior.
+#[derive(Debug)]
+pub struct CredentialStore {
+    hashes: HashMap<String, String, sz::BuildSzHasher>,
+}
+
+impl CredentialStore {
+    fn load_from_env() -> Self {
+        let hashes = env::var(config::AUTH_CREDENTIALS_PATH_ENV)
+            .ok()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|contents| parse_credentials(&contents))
+            .unwrap_or_default();
+        Self { hashes }
+    }
+
+    /// Returns `true` if `username` has a stored credential and therefore
+    /// must present a successful `AUTH` before `JOIN` will succeed.
+    #[must_use]
+    pub fn requires_auth(&self, username: &Username) -> bool {
+        self.hashes.contains_key(&my_string::to_lowercase(&username.to_string()))
+    }
+
+    /// Verifies `password` against the stored Argon2 hash for `username`.
+    ///
+    /// Runs the verification on a blocking thread since Argon2 is
+    /// deliberately slow and must not stall the async runtime. An unknown
+    /// username is verified against [`DUMMY_HASH`] instead of short-circuiting,
+    /// so it fails via the same code path and in about the same time as a
+    /// wrong password for a known username — this prevents username
+    /// enumeration via `AUTH` timing or error shape.
+    pub async fn verify(&self, username: &Username, password: &str) -> Result<(), Error> {
+        let stored = self
+            .hashes
+            .get(&my_string::to_lowercase(&username.to_string()))
+            .cloned()
+            .unwrap_or_else(|| DUMMY_HASH.to_string());
+        let password = password.to_string();
+
+        tokio::task::spawn_blocking(move || {
+            let hash = PasswordHash::new(&stored).map_err(|_| Error::MalformedHash)?;
+            Argon2::default()
+                .verify_password(password.as_bytes(), &hash)
+                .map_err(|_| Error::AuthFailed)
+        })
+        .await
+        .unwrap_or(Err(Error::AuthFailed))
+    }
+}
+
+impl Default for CredentialStore {
+    fn default() -> Self {
+        Self {
+            hashes: HashMap::with_hasher(sz::BuildSzHasher::default()),
+        }
+    }
+}
+
+fn parse_credentials(contents: &str) -> HashMap<String, String, sz::BuildSzHasher> {
+    let mut hashes = HashMap::with_hasher(sz::BuildSzHasher::default());
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Some((name, hash)) = line.split_once(':') {
+            hashes.insert(my_string::to_lowercase(name.trim()), hash.trim().to_string());
+        }
+    }
+    hashes
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use super::*;
+
+    fn store_from(contents: &str) -> CredentialStore {
+        CredentialStore {
+            hashes: parse_credentials(contents),
+        }
+    }
+
+    #[test]
+    fn test_parse_credentials_skips_blank_and_comment_lines() {
+        let store = store_from("\n# comment\nalice:fakehash\n\n");
+        assert!(store.requires_auth(&Username::new("alice").unwrap()));
+    }
+
+    #[test]
+    fn test_requires_auth_is_case_insensitive() {
+        let store = store_from("Alice:fakehash");
+        assert!(store.requires_auth(&Username::new("alice").unwrap()));
+        assert!(store.requires_auth(&Username::new("ALICE").unwrap()));
+    }
+
+    #[test]
+    fn test_requires_auth_false_without_entry() {
+        let store = store_from("alice:fakehash");
+        assert!(!store.requires_auth(&Username::new("bob").unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_unknown_username_as_auth_failed() {
+        // An unknown username must fail the same way a wrong password does
+        // (not a distinct error), so `AUTH` can't be used to enumerate users.
+        let store = store_from("alice:fakehash");
+        let err = store.verify(&Username::new("bob").unwrap(), "anything").await.unwrap_err();
+        assert_eq!(err, Error::AuthFailed);
+    }
+
+    #[tokio::test]
+    async fn test_verify_rejects_malformed_hash() {
+        let store = store_from("alice:not-a-phc-hash");
+        let err = store.verify(&Username::new("alice").unwrap(), "anything").await.unwrap_err();
+        assert_eq!(err, Error::MalformedHash);
+    }
+
+    #[test]
+    fn test_decode_plain_extracts_authcid_and_password() {
+        let payload = BASE64.encode(b"alice\0alice\0hunter2");
+        let (authcid, password) = decode_plain(&payload).unwrap();
+        assert_eq!(authcid, "alice");
+        assert_eq!(password, "hunter2");
+    }
+
+    #[test]
+    fn test_decode_plain_ignores_authzid() {
+        let payload = BASE64.encode(b"admin\0alice\0hunter2");
+        let (authcid, password) = decode_plain(&payload).unwrap();
+        assert_eq!(authcid, "alice");
+        assert_eq!(password, "hunter2");
+    }
+
+    #[test]
+    fn test_decode_plain_rejects_invalid_base64() {
+        let err = decode_plain("not base64!!").unwrap_err();
+        assert_eq!(err, Error::MalformedPlainPayload);
+    }
+
+    #[test]
+    fn test_decode_plain_rejects_missing_fields() {
+        let payload = BASE64.encode(b"alice\0hunter2");
+        let err = decode_plain(&payload).unwrap_err();
+        assert_eq!(err, Error::MalformedPlainPayload);
+    }
+}
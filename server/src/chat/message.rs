@@ -1,31 +1,43 @@
-use stringzilla::sz;
+use jiff::Timestamp;
 
 use crate::chat::user::{User, Username};
 
+/// Wire frame field separator: a control byte (US, unit separator) that
+/// can't appear in a validated username or ordinary message content, so
+/// splitting on it can't silently misparse the way the old single-colon
+/// framing did whenever content itself contained a colon.
+const FIELD_SEP: char = '\u{1f}';
+
 #[derive(Debug, Clone)]
 // ChatMessage alive as long as User
 pub struct ChatMessage<'a> {
     pub sender: &'a User,
+    pub ts: Timestamp,
     pub content: String,
 }
 
 impl<'a> ChatMessage<'a> {
-    pub const fn new(sender: &'a User, content: String) -> Self {
-        Self { sender, content }
+    pub fn new(sender: &'a User, content: String) -> Self {
+        Self {
+            sender,
+            ts: Timestamp::now(),
+            content,
+        }
     }
 
     // channel ingress
     pub fn serialize(&self) -> String {
-        format!("{}:{}", self.sender.get_username(), self.content)
+        format!("{}{FIELD_SEP}{}{FIELD_SEP}{}", self.sender.get_username(), self.ts, self.content)
     }
 
     // channel egress
-    pub fn deserialize(s: &str) -> Option<(Username, String)> {
-        let idx = sz::find(s, ":")?;
-        let sender_str = s.get(..idx)?;
-        let sender = Username::new(sender_str).ok()?;
-        let content = s.get(idx.saturating_add(1)..)?.to_string();
-        Some((sender, content))
+    pub fn deserialize(s: &str) -> Option<(Username, Timestamp, String)> {
+        let mut fields = s.splitn(3, FIELD_SEP);
+        let sender = Username::new(fields.next()?).ok()?;
+        let ts_str = fields.next()?;
+        let content = fields.next()?.to_string();
+        let ts = ts_str.parse::<Timestamp>().unwrap_or_else(|_| Timestamp::now());
+        Some((sender, ts, content))
     }
 }
 
@@ -41,7 +53,7 @@ mod tests {
         let registry = UserRegistry::new();
         let (tx, _rx) = channel::unbounded();
         let username = Username::new(name).unwrap();
-        let user = registry.register(&username, tx).unwrap();
+        let user = registry.register(&username, tx, false).unwrap();
         (registry, user)
     }
 
@@ -55,11 +67,14 @@ mod tests {
     }
 
     #[test]
-    fn test_chat_message_serialize() {
+    fn test_chat_message_serialize_round_trips() {
         let (_registry, user) = create_test_registry_and_user("bob");
         let message = ChatMessage::new(&user, "test message".to_string());
 
-        assert_eq!(message.serialize(), "bob:test message");
+        let (sender, ts, content) = ChatMessage::deserialize(&message.serialize()).unwrap();
+        assert_eq!(sender.to_string(), "bob");
+        assert_eq!(ts, message.ts);
+        assert_eq!(content, "test message");
     }
 
     #[test]
@@ -67,7 +82,9 @@ mod tests {
         let (_registry, user) = create_test_registry_and_user("charlie");
         let message = ChatMessage::new(&user, String::new());
 
-        assert_eq!(message.serialize(), "charlie:");
+        let (sender, _ts, content) = ChatMessage::deserialize(&message.serialize()).unwrap();
+        assert_eq!(sender.to_string(), "charlie");
+        assert_eq!(content, "");
     }
 
     #[test]
@@ -75,54 +92,65 @@ mod tests {
         let (_registry, user) = create_test_registry_and_user("dave");
         let message = ChatMessage::new(&user, "time: 12:30:00".to_string());
 
-        assert_eq!(message.serialize(), "dave:time: 12:30:00");
+        let (_sender, _ts, content) = ChatMessage::deserialize(&message.serialize()).unwrap();
+        assert_eq!(content, "time: 12:30:00");
     }
 
     #[test]
     fn test_chat_message_deserialize_valid() {
-        let result = ChatMessage::deserialize("alice:hello world");
+        let result = ChatMessage::deserialize("alice\u{1f}2024-01-01T00:00:00Z\u{1f}hello world");
 
         assert!(result.is_some());
-        let (username, content) = result.unwrap();
+        let (username, _ts, content) = result.unwrap();
         assert_eq!(username.to_string(), "alice");
         assert_eq!(content, "hello world");
     }
 
     #[test]
     fn test_chat_message_deserialize_empty_content() {
-        let result = ChatMessage::deserialize("bob:");
+        let result = ChatMessage::deserialize("bob\u{1f}2024-01-01T00:00:00Z\u{1f}");
 
         assert!(result.is_some());
-        let (username, content) = result.unwrap();
+        let (username, _ts, content) = result.unwrap();
         assert_eq!(username.to_string(), "bob");
         assert_eq!(content, "");
     }
 
     #[test]
     fn test_chat_message_deserialize_colons_in_content() {
-        let result = ChatMessage::deserialize("charlie:time: 12:30:00");
+        let result = ChatMessage::deserialize("charlie\u{1f}2024-01-01T00:00:00Z\u{1f}time: 12:30:00");
 
         assert!(result.is_some());
-        let (username, content) = result.unwrap();
+        let (username, _ts, content) = result.unwrap();
         assert_eq!(username.to_string(), "charlie");
         assert_eq!(content, "time: 12:30:00");
     }
 
     #[test]
-    fn test_chat_message_deserialize_no_colon() {
-        let result = ChatMessage::deserialize("invalid message");
-        assert!(result.is_none());
+    fn test_chat_message_deserialize_falls_back_to_now_on_bad_timestamp() {
+        let result = ChatMessage::deserialize("bob\u{1f}not-a-timestamp\u{1f}hi");
+
+        assert!(result.is_some());
+        let (username, _ts, content) = result.unwrap();
+        assert_eq!(username.to_string(), "bob");
+        assert_eq!(content, "hi");
+    }
+
+    #[test]
+    fn test_chat_message_deserialize_missing_fields_rejected() {
+        assert!(ChatMessage::deserialize("invalid message").is_none());
+        assert!(ChatMessage::deserialize("bob\u{1f}2024-01-01T00:00:00Z").is_none());
     }
 
     #[test]
     fn test_chat_message_deserialize_empty_username() {
-        let result = ChatMessage::deserialize(":message");
+        let result = ChatMessage::deserialize("\u{1f}2024-01-01T00:00:00Z\u{1f}message");
         assert!(result.is_none());
     }
 
     #[test]
     fn test_chat_message_deserialize_invalid_username() {
-        let result = ChatMessage::deserialize("user@invalid:message");
+        let result = ChatMessage::deserialize("user@invalid\u{1f}2024-01-01T00:00:00Z\u{1f}message");
         assert!(result.is_none());
     }
 
@@ -0,0 +1,166 @@
+//! Prometheus metrics for operational visibility into connection counts,
+//! message throughput, and dispatch failures.
+//!
+//! Metrics live in a process-wide [`prometheus::Registry`], the same
+//! singleton-via-`LazyLock` pattern used for [`super::room::get_room`] and
+//! [`super::user::get_registry`]. `MessageBroker` holds the accessor and
+//! updates the counters at the call sites that already track these events;
+//! [`spawn_metrics_server`] serves them as plain text over `/metrics`.
+
+use std::{net::SocketAddr, sync::LazyLock};
+
+use prometheus::{Encoder, IntCounter, IntGauge, Registry, TextEncoder};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tracing::{error, info, warn};
+
+const DEFAULT_METRICS_ADDR: &str = "127.0.0.1:9090";
+const METRICS_ADDR_ENV: &str = "CHAT_METRICS_ADDR";
+
+static METRICS: LazyLock<Metrics> = LazyLock::new(Metrics::new);
+
+pub fn get_metrics() -> &'static Metrics {
+    &METRICS
+}
+
+pub struct Metrics {
+    registry: Registry,
+    pub connected_users: IntGauge,
+    pub messages_forwarded: IntCounter,
+    pub messages_dispatched: IntCounter,
+    pub fanout_total: IntCounter,
+    pub deserialize_failures: IntCounter,
+    pub rate_limit_rejections: IntCounter,
+    pub oversized_drops: IntCounter,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+
+        let connected_users =
+            IntGauge::new("chat_connected_users", "Currently registered user connections").expect("valid metric");
+        let messages_forwarded = IntCounter::new(
+            "chat_messages_forwarded_total",
+            "Messages forwarded into the room channel",
+        )
+        .expect("valid metric");
+        let messages_dispatched = IntCounter::new(
+            "chat_messages_dispatched_total",
+            "Messages successfully dispatched by the broker",
+        )
+        .expect("valid metric");
+        let fanout_total = IntCounter::new(
+            "chat_fanout_total",
+            "Per-recipient deliveries across all dispatched messages",
+        )
+        .expect("valid metric");
+        let deserialize_failures = IntCounter::new(
+            "chat_deserialize_failures_total",
+            "Messages that failed to deserialize in the dispatcher",
+        )
+        .expect("valid metric");
+        let rate_limit_rejections = IntCounter::new(
+            "chat_rate_limit_rejections_total",
+            "Requests rejected by a rate limiter",
+        )
+        .expect("valid metric");
+        let oversized_drops = IntCounter::new(
+            "chat_oversized_message_drops_total",
+            "Lines dropped for exceeding the max length",
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(connected_users.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(messages_forwarded.clone()),
+            Box::new(messages_dispatched.clone()),
+            Box::new(fanout_total.clone()),
+            Box::new(deserialize_failures.clone()),
+            Box::new(rate_limit_rejections.clone()),
+            Box::new(oversized_drops.clone()),
+        ] {
+            if let Err(e) = registry.register(collector) {
+                warn!("Failed to register metric: {e}");
+            }
+        }
+
+        Self {
+            registry,
+            connected_users,
+            messages_forwarded,
+            messages_dispatched,
+            fanout_total,
+            deserialize_failures,
+            rate_limit_rejections,
+            oversized_drops,
+        }
+    }
+
+    fn render(&self) -> String {
+        let metric_families = self.registry.gather();
+        let mut buffer = Vec::new();
+        if let Err(e) = TextEncoder::new().encode(&metric_families, &mut buffer) {
+            error!("Failed to encode metrics: {e}");
+        }
+        String::from_utf8(buffer).unwrap_or_default()
+    }
+}
+
+/// Starts the `/metrics` HTTP listener alongside the dispatcher. Binds to
+/// `CHAT_METRICS_ADDR`, falling back to `DEFAULT_METRICS_ADDR`. Runs for the
+/// lifetime of the process; failures to bind are logged, not fatal, since
+/// metrics are an operational aid rather than something clients depend on.
+pub fn spawn_metrics_server(metrics: &'static Metrics) {
+    let addr: SocketAddr = std::env::var(METRICS_ADDR_ENV)
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(|| DEFAULT_METRICS_ADDR.parse().expect("default metrics addr is valid"));
+
+    tokio::spawn(async move {
+        let listener = match TcpListener::bind(addr).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to bind metrics listener on {addr}: {e}");
+                return;
+            }
+        };
+        info!("Metrics endpoint listening on http://{addr}/metrics");
+
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    tokio::spawn(serve_one(stream, metrics));
+                }
+                Err(e) => warn!("Failed to accept metrics connection: {e}"),
+            }
+        }
+    });
+}
+
+async fn serve_one(stream: TcpStream, metrics: &'static Metrics) {
+    let (reader, mut writer) = stream.into_split();
+    let mut reader = BufReader::new(reader);
+    let mut line = String::new();
+
+    if reader.read_line(&mut line).await.is_err() {
+        return;
+    }
+
+    let response = if line.starts_with("GET /metrics ") {
+        let body = metrics.render();
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            body.len(),
+            body
+        )
+    } else {
+        "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+    };
+
+    if let Err(e) = writer.write_all(response.as_bytes()).await {
+        warn!("Failed to write metrics response: {e}");
+    }
+}
@@ -0,0 +1,13 @@
+pub mod broker;
+pub mod client_protocol;
+pub mod connection;
+pub mod credentials;
+pub mod message;
+pub mod metrics;
+pub mod quic;
+pub mod rate_limiter;
+pub mod room;
+pub mod room_registry;
+pub mod server_protocol;
+pub mod string;
+pub mod user;
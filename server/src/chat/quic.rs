@@ -0,0 +1,95 @@
+//! Optional QUIC listener, run alongside the always-on TCP listener when
+//! `CHAT_QUIC_CERT_PATH`/`CHAT_QUIC_KEY_PATH` are configured (see
+//! `common::config::quic_cert_paths`). Negotiates the same `simple-chat/1`
+//! ALPN as the client's QUIC transport and hands each connection's first
+//! bidirectional stream to the same [`connection::handle_connection`] the
+//! TCP listener uses, so the session handling is written once.
+
+use std::{fs, net::SocketAddr, path::Path, sync::Arc};
+
+use thiserror::Error;
+use tokio::sync::Semaphore;
+use tracing::{error, info, warn};
+
+use crate::chat::connection::handle_connection;
+
+/// ALPN protocol identifier negotiated over QUIC; must match the client's.
+const QUIC_ALPN: &[u8] = b"simple-chat/1";
+
+#[derive(Debug, Error)]
+pub enum QuicServerError {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("TLS configuration error: {0}")]
+    Tls(String),
+}
+
+/// Builds the QUIC endpoint from a PEM certificate chain and private key at
+/// `cert_path`/`key_path`, binds it to `addr`, and serves connections until
+/// the endpoint is closed, dispatching each to the shared connection
+/// handling logic used by the TCP listener.
+///
+/// # Errors
+///
+/// Returns an error if the certificate/key can't be read or parsed, or if
+/// the UDP socket can't be bound.
+pub async fn serve(addr: SocketAddr, cert_path: &Path, key_path: &Path, semaphore: Arc<Semaphore>) -> Result<(), QuicServerError> {
+    let server_config = build_server_config(cert_path, key_path)?;
+    let endpoint = quinn::Endpoint::server(server_config, addr)?;
+    info!("Chat server listening for QUIC on {addr}");
+
+    while let Some(incoming) = endpoint.accept().await {
+        let permit = if let Ok(permit) = semaphore.clone().try_acquire_owned() {
+            permit
+        } else {
+            warn!("Connection limit reached, new QUIC connection may be delayed");
+            match semaphore.clone().acquire_owned().await {
+                Ok(permit) => permit,
+                Err(_) => {
+                    error!("Semaphore closed unexpectedly");
+                    return Ok(());
+                }
+            }
+        };
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            if let Err(e) = accept_quic_connection(incoming).await {
+                error!("QUIC connection error: {e}");
+            }
+        });
+    }
+
+    Ok(())
+}
+
+async fn accept_quic_connection(incoming: quinn::Incoming) -> Result<(), QuicServerError> {
+    let connection = incoming.await.map_err(|e| QuicServerError::Tls(e.to_string()))?;
+    let addr = connection.remote_address();
+    let (send, recv) = connection.accept_bi().await.map_err(|e| QuicServerError::Tls(e.to_string()))?;
+
+    handle_connection(Box::new(recv), Box::new(send), addr).await;
+    Ok(())
+}
+
+fn build_server_config(cert_path: &Path, key_path: &Path) -> Result<quinn::ServerConfig, QuicServerError> {
+    let cert_pem = fs::read(cert_path)?;
+    let key_pem = fs::read(key_path)?;
+
+    let certs: Vec<_> = rustls_pemfile::certs(&mut cert_pem.as_slice()).flatten().collect();
+    let key = rustls_pemfile::private_key(&mut key_pem.as_slice())
+        .map_err(|e| QuicServerError::Tls(e.to_string()))?
+        .ok_or_else(|| QuicServerError::Tls("no private key found in CHAT_QUIC_KEY_PATH file".to_string()))?;
+
+    let mut tls_config = rustls::ServerConfig::builder()
+        .with_no_client_auth()
+        .with_single_cert(certs, key)
+        .map_err(|e| QuicServerError::Tls(e.to_string()))?;
+    tls_config.alpn_protocols = vec![QUIC_ALPN.to_vec()];
+
+    let quic_server_config =
+        quinn::crypto::rustls::QuicServerConfig::try_from(tls_config).map_err(|e| QuicServerError::Tls(e.to_string()))?;
+
+    Ok(quinn::ServerConfig::with_crypto(Arc::new(quic_server_config)))
+}
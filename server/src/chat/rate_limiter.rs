@@ -1,17 +1,50 @@
-use std::num::NonZeroU32;
+use std::{
+    hash::Hash,
+    num::NonZeroU32,
+    path::PathBuf,
+    sync::{Arc, LazyLock},
+    time::Duration,
+};
 
 use common::consts::{MAX_MESSAGES_PER_SECOND, MESSAGE_BURST_CAPACITY};
 use governor::{
     Quota, RateLimiter as GovRateLimiter,
     clock::DefaultClock,
-    state::{InMemoryState, NotKeyed},
+    state::{InMemoryState, NotKeyed, keyed::DashMapStateStore},
 };
+use parking_lot::RwLock;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use crate::chat::user::Username;
 
 type DirectRateLimiter = GovRateLimiter<NotKeyed, InMemoryState, DefaultClock>;
 
+/// How often [`spawn_config_watcher`] re-reads the rate-limit config file to
+/// check for changes. A poll rather than a filesystem-event watch, to avoid
+/// adding a platform-specific file-watching dependency for what is, at this
+/// scale, an infrequent operator-driven edit.
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+static MESSAGE_RATE_LIMITER: LazyLock<KeyedRateLimiter<Username>> = LazyLock::new(KeyedRateLimiter::new);
+
+/// The server-wide per-user message rate limiter: one token bucket per
+/// username, shared by every connection authenticated as that user, so
+/// `MAX_MESSAGES_PER_SECOND` is enforced per person rather than per
+/// connection (which a reconnect would otherwise reset).
+pub fn get_message_rate_limiter() -> &'static KeyedRateLimiter<Username> {
+    &MESSAGE_RATE_LIMITER
+}
+
+/// A token-bucket rate limiter whose quota can be swapped live via
+/// [`reconfigure`](Self::reconfigure) without disrupting callers already
+/// waiting in [`acquire`](Self::acquire): each call clones the current
+/// `Arc<DirectRateLimiter>` before awaiting it, so an in-flight future keeps
+/// running against the quota it started with rather than being dropped or
+/// panicking when the bucket underneath it is replaced.
 #[derive(Debug)]
 pub struct RateLimiter {
-    inner: DirectRateLimiter,
+    inner: RwLock<Arc<DirectRateLimiter>>,
 }
 
 impl RateLimiter {
@@ -20,29 +53,170 @@ impl RateLimiter {
         Self::with_config(MAX_MESSAGES_PER_SECOND, MESSAGE_BURST_CAPACITY)
     }
 
+    #[must_use]
+    pub fn with_config(rate_per_second: u32, burst_capacity: u32) -> Self {
+        Self {
+            inner: RwLock::new(Arc::new(build_direct_limiter(rate_per_second, burst_capacity))),
+        }
+    }
+
+    #[must_use]
+    pub fn try_acquire(&self) -> bool {
+        self.inner.read().check().is_ok()
+    }
+
+    pub async fn acquire(&self) {
+        let limiter = Arc::clone(&self.inner.read());
+        limiter.until_ready().await;
+    }
+
+    /// Atomically swaps in a freshly built limiter with the given quota.
+    /// Futures already parked inside a prior [`acquire`](Self::acquire) call
+    /// hold their own clone of the old limiter and are unaffected; only
+    /// calls made after this returns observe the new quota.
+    pub fn reconfigure(&self, rate_per_second: u32, burst_capacity: u32) {
+        *self.inner.write() = Arc::new(build_direct_limiter(rate_per_second, burst_capacity));
+    }
+}
+
+fn build_direct_limiter(rate_per_second: u32, burst_capacity: u32) -> DirectRateLimiter {
+    let rate = NonZeroU32::new(rate_per_second).unwrap_or(NonZeroU32::MIN);
+    let burst = NonZeroU32::new(burst_capacity).unwrap_or(NonZeroU32::MIN);
+
+    let quota = Quota::per_second(rate).allow_burst(burst);
+    GovRateLimiter::direct(quota)
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Contents of the small TOML file [`spawn_config_watcher`] polls for live
+/// rate-limit tuning, e.g.:
+///
+/// ```toml
+/// version = 2
+/// max_messages_per_second = 20
+/// burst_capacity = 40
+/// ```
+///
+/// `version` must strictly increase between applied edits; this rejects
+/// both malformed files (which fail to parse at all) and stale or
+/// accidentally-reverted ones (same or lower version than the last one
+/// applied).
+#[derive(Debug, Clone, Deserialize)]
+struct RateLimitConfigFile {
+    version: u32,
+    max_messages_per_second: u32,
+    burst_capacity: u32,
+}
+
+/// Polls `path` every [`CONFIG_POLL_INTERVAL`] and calls
+/// [`RateLimiter::reconfigure`] on `limiter` whenever it contains a valid,
+/// higher-`version` config than the last one applied. Runs for the lifetime
+/// of the process; read/parse failures and non-increasing versions are
+/// logged and skipped rather than applied, so a bad edit can't take the
+/// limiter down.
+pub fn spawn_config_watcher(path: PathBuf, limiter: &'static RateLimiter) {
+    tokio::spawn(async move {
+        let mut last_applied_version: Option<u32> = None;
+        let mut interval = tokio::time::interval(CONFIG_POLL_INTERVAL);
+
+        loop {
+            interval.tick().await;
+
+            let contents = match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => contents,
+                Err(e) => {
+                    warn!("Failed to read rate-limit config at {}: {e}", path.display());
+                    continue;
+                }
+            };
+
+            let parsed: RateLimitConfigFile = match toml::from_str(&contents) {
+                Ok(parsed) => parsed,
+                Err(e) => {
+                    warn!("Malformed rate-limit config at {}: {e}", path.display());
+                    continue;
+                }
+            };
+
+            if last_applied_version.is_some_and(|applied| parsed.version <= applied) {
+                warn!(
+                    "Ignoring rate-limit config at {} with non-increasing version {} (last applied {:?})",
+                    path.display(),
+                    parsed.version,
+                    last_applied_version
+                );
+                continue;
+            }
+
+            limiter.reconfigure(parsed.max_messages_per_second, parsed.burst_capacity);
+            info!(
+                "Applied rate-limit config version {} ({} msgs/sec, burst {})",
+                parsed.version, parsed.max_messages_per_second, parsed.burst_capacity
+            );
+            last_applied_version = Some(parsed.version);
+        }
+    });
+}
+
+/// Per-key rate limiter backed by governor's `DashMap`-backed keyed state:
+/// each key (e.g. a [`Username`]) gets its own independent token bucket,
+/// instead of every caller sharing the single bucket [`RateLimiter`] gives
+/// you. Use this where throttling needs to track an abuser across multiple
+/// connections; use `RateLimiter` where one bucket per connection is enough.
+pub struct KeyedRateLimiter<K>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    inner: GovRateLimiter<K, DashMapStateStore<K>, DefaultClock>,
+}
+
+impl<K> KeyedRateLimiter<K>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
+    #[must_use]
+    pub fn new() -> Self {
+        Self::with_config(MAX_MESSAGES_PER_SECOND, MESSAGE_BURST_CAPACITY)
+    }
+
     #[must_use]
     pub fn with_config(rate_per_second: u32, burst_capacity: u32) -> Self {
         let rate = NonZeroU32::new(rate_per_second).unwrap_or(NonZeroU32::MIN);
         let burst = NonZeroU32::new(burst_capacity).unwrap_or(NonZeroU32::MIN);
 
         let quota = Quota::per_second(rate).allow_burst(burst);
-        let limiter = GovRateLimiter::direct(quota);
+        let limiter = GovRateLimiter::dashmap(quota);
 
         Self { inner: limiter }
     }
 
     #[must_use]
-    #[allow(dead_code)]
-    pub fn try_acquire(&self) -> bool {
-        self.inner.check().is_ok()
+    pub fn try_acquire(&self, key: &K) -> bool {
+        self.inner.check_key(key).is_ok()
     }
 
-    pub async fn acquire(&self) {
-        self.inner.until_ready().await;
+    pub async fn acquire(&self, key: &K) {
+        self.inner.until_key_ready(key).await;
+    }
+
+    /// Drops tracked buckets for keys that haven't been touched recently,
+    /// so the backing `DashMap` doesn't grow without bound as users
+    /// disconnect. Meant to be called periodically (e.g. from a
+    /// housekeeping tick), not on the per-message hot path.
+    pub fn retain_recent(&self) {
+        self.inner.retain_recent();
     }
 }
 
-impl Default for RateLimiter {
+impl<K> Default for KeyedRateLimiter<K>
+where
+    K: Hash + Eq + Clone + Send + Sync + 'static,
+{
     fn default() -> Self {
         Self::new()
     }
@@ -109,6 +283,41 @@ mod tests {
         assert!(limiter.try_acquire());
     }
 
+    #[test]
+    fn test_reconfigure_replaces_quota() {
+        let limiter = RateLimiter::with_config(1, 1);
+        assert!(limiter.try_acquire());
+        assert!(!limiter.try_acquire());
+
+        limiter.reconfigure(1, 5);
+        for _ in 0..5 {
+            assert!(limiter.try_acquire());
+        }
+        assert!(!limiter.try_acquire());
+    }
+
+    #[test]
+    fn test_rate_limit_config_file_parses_toml() {
+        let parsed: RateLimitConfigFile = toml::from_str(
+            r#"
+            version = 2
+            max_messages_per_second = 20
+            burst_capacity = 40
+            "#,
+        )
+        .expect("should parse");
+
+        assert_eq!(parsed.version, 2);
+        assert_eq!(parsed.max_messages_per_second, 20);
+        assert_eq!(parsed.burst_capacity, 40);
+    }
+
+    #[test]
+    fn test_rate_limit_config_file_rejects_missing_field() {
+        let result: Result<RateLimitConfigFile, _> = toml::from_str("version = 1\nmax_messages_per_second = 10\n");
+        assert!(result.is_err());
+    }
+
     #[tokio::test]
     async fn test_async_acquire_throttles() {
         let limiter = RateLimiter::with_config(10, 1);
@@ -125,4 +334,49 @@ mod tests {
             "Should handle throttling, waited {elapsed:?}"
         );
     }
+
+    #[test]
+    fn test_keyed_limiter_tracks_keys_independently() {
+        let limiter = KeyedRateLimiter::with_config(1, 2);
+
+        assert!(limiter.try_acquire(&"alice"));
+        assert!(limiter.try_acquire(&"alice"));
+        assert!(!limiter.try_acquire(&"alice"));
+
+        // A different key has its own, untouched bucket.
+        assert!(limiter.try_acquire(&"bob"));
+        assert!(limiter.try_acquire(&"bob"));
+        assert!(!limiter.try_acquire(&"bob"));
+    }
+
+    #[test]
+    fn test_keyed_limiter_default_impl() {
+        let limiter: KeyedRateLimiter<&str> = KeyedRateLimiter::default();
+
+        assert!(limiter.try_acquire(&"anyone"));
+    }
+
+    #[tokio::test]
+    async fn test_keyed_limiter_async_acquire_throttles() {
+        let limiter = KeyedRateLimiter::with_config(10, 1);
+
+        limiter.acquire(&"alice").await;
+
+        let start = std::time::Instant::now();
+
+        limiter.acquire(&"alice").await;
+        let elapsed = start.elapsed();
+
+        assert!(
+            elapsed.as_millis() >= 90,
+            "Should handle throttling, waited {elapsed:?}"
+        );
+    }
+
+    #[test]
+    fn test_keyed_limiter_retain_recent_does_not_panic() {
+        let limiter = KeyedRateLimiter::with_config(10, 10);
+        let _ = limiter.try_acquire(&"alice");
+        limiter.retain_recent();
+    }
 }
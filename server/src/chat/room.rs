@@ -11,6 +11,10 @@ use uuid::Uuid;
 
 const DEFAULT_BUFFER_LENGTH: u16 = u16::MAX;
 
+/// A message fanned out from one sender to many recipients, e.g. a room
+/// broadcast or direct message body delivered over a per-user channel.
+pub type OneToMany = String;
+
 #[derive(this_error, Debug)]
 pub enum Error {
     #[error("room busy, message not sent: {0}")]
@@ -42,6 +46,11 @@ pub trait MessageReceiver: Send + Sync {
 pub trait MessageQueue: Send + Sync {
     fn send_timeout(&self, msg: String, timeout: Duration) -> Result<(), Error>;
     fn receiver(&self) -> &dyn MessageReceiver;
+
+    /// Drops the send side, so the dispatcher's blocking receiver observes
+    /// `RecvError::Disconnected` and exits its loop. Used to shut the
+    /// dispatcher thread down in bounded time.
+    fn close(&self);
 }
 
 impl MessageReceiver for Receiver<String> {
@@ -132,6 +141,12 @@ impl MessageQueue for Room {
     fn receiver(&self) -> &dyn MessageReceiver {
         &self.receiver
     }
+
+    fn close(&self) {
+        if let Ok(mut guard) = self.sender.write() {
+            *guard = None;
+        }
+    }
 }
 
 #[cfg(test)]
@@ -169,6 +184,18 @@ mod tests {
         assert_eq!(received, msg);
     }
 
+    #[test]
+    fn test_room_close_disconnects_receiver() {
+        let room = Room::new(5);
+        room.close();
+
+        let err = room.send_timeout("too late".to_string(), Duration::from_millis(100)).unwrap_err();
+        assert!(matches!(err, Error::Closed(_)));
+
+        let recv_err = room.receiver().recv_timeout(Duration::from_millis(100)).unwrap_err();
+        assert_eq!(recv_err, RecvError::Disconnected);
+    }
+
     #[test]
     fn test_room_display() {
         let room = Room::new(1);
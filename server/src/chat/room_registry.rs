@@ -0,0 +1,458 @@
+use std::{
+    collections::{HashMap, VecDeque, hash_map::Entry},
+    fmt::{Display, Formatter},
+    sync::{Arc, LazyLock},
+    time::{Duration, SystemTime},
+};
+
+use common::config;
+use futures::stream::{self, StreamExt};
+use parking_lot::RwLock;
+use stringzilla::sz;
+use thiserror::Error as this_error;
+
+use super::string as my_string;
+use crate::chat::{
+    room,
+    user::{User, Username},
+};
+
+const SEND_TIMEOUT: Duration = Duration::from_millis(100);
+const LOCK_TIMEOUT: Duration = Duration::from_millis(50);
+const CONCURRENT_LIMIT: usize = 1024;
+
+/// Maximum number of messages retained per room for `HISTORY` replay,
+/// configurable via `CHAT_ROOM_HISTORY_CAPACITY` (see [`config::room_history_capacity`]).
+pub static HISTORY_CAPACITY: LazyLock<usize> = LazyLock::new(config::room_history_capacity);
+
+/// Number of recent messages automatically replayed to a connection right
+/// after it joins a room, so it doesn't see a blank room before any `HISTORY`
+/// request.
+pub const JOIN_HISTORY_REPLAY_LIMIT: usize = 20;
+
+static REGISTRY: LazyLock<RoomRegistry> = LazyLock::new(RoomRegistry::new);
+
+pub fn get_registry() -> &'static RoomRegistry {
+    &REGISTRY
+}
+
+#[derive(Debug, Clone, this_error, PartialEq, Eq)]
+pub enum Error {
+    #[error("room name cannot be empty")]
+    RoomEmpty,
+
+    #[error("registry lock timeout")]
+    LockTimeout,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RoomName(String);
+
+impl RoomName {
+    pub fn new(s: impl Into<String>) -> Result<Self, Error> {
+        let s = s.into();
+        let trimmed = s.trim();
+        if trimmed.is_empty() {
+            return Err(Error::RoomEmpty);
+        }
+        Ok(Self(trimmed.to_string()))
+    }
+}
+
+impl Display for RoomName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct NormalizedRoomKey(String);
+
+impl NormalizedRoomKey {
+    fn from_name(name: &RoomName) -> Self {
+        Self(my_string::to_lowercase(&name.0))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct MemberKey(String);
+
+impl MemberKey {
+    fn from_username(username: &Username) -> Self {
+        Self(my_string::to_lowercase(&username.to_string()))
+    }
+}
+
+/// A single retained chat line, kept so a newly-joined member can replay
+/// recent room activity via `HISTORY`.
+#[derive(Debug, Clone)]
+pub struct StoredMessage {
+    pub ts: SystemTime,
+    pub sender: Username,
+    pub body: String,
+}
+
+#[derive(Debug)]
+struct RoomState {
+    members: RwLock<HashMap<MemberKey, User, sz::BuildSzHasher>>,
+    history: RwLock<VecDeque<StoredMessage>>,
+}
+
+impl RoomState {
+    fn new() -> Self {
+        Self {
+            members: RwLock::new(HashMap::with_hasher(sz::BuildSzHasher::default())),
+            history: RwLock::new(VecDeque::with_capacity(*HISTORY_CAPACITY)),
+        }
+    }
+}
+
+/// Selects which retained messages a `HISTORY` request returns.
+#[derive(Debug, Clone, Copy)]
+pub enum HistoryQuery {
+    /// The most recent `n` messages, oldest first.
+    Latest(usize),
+    /// Every retained message strictly newer than `ts`, oldest first.
+    After(SystemTime),
+}
+
+/// Shared handle to a single room's state, cloned out of the registry
+/// under a short-lived read lock so callers can broadcast without holding
+/// the registry lock for the duration of the send.
+pub type RoomHandle = Arc<RoomState>;
+
+#[derive(Debug)]
+pub struct RoomRegistry {
+    rooms: RwLock<HashMap<NormalizedRoomKey, RoomHandle, sz::BuildSzHasher>>,
+}
+
+impl RoomRegistry {
+    pub fn new() -> Self {
+        Self {
+            rooms: RwLock::new(HashMap::with_hasher(sz::BuildSzHasher::default())),
+        }
+    }
+
+    fn handle_for(&self, room: &RoomName) -> Result<RoomHandle, Error> {
+        let key = NormalizedRoomKey::from_name(room);
+        if let Some(handle) = self.rooms.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?.get(&key) {
+            return Ok(Arc::clone(handle));
+        }
+
+        let handle = Arc::clone(
+            self.rooms
+                .try_write_for(LOCK_TIMEOUT)
+                .ok_or(Error::LockTimeout)?
+                .entry(key)
+                .or_insert_with(|| Arc::new(RoomState::new())),
+        );
+        Ok(handle)
+    }
+
+    /// Adds `user` as a member of `room`, creating the room if needed.
+    /// Returns `true` if the user was not already a member.
+    pub fn join(&self, room: &RoomName, user: &User) -> Result<bool, Error> {
+        let handle = self.handle_for(room)?;
+        let mut members = handle.members.try_write_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+        match members.entry(MemberKey::from_username(&user.get_username())) {
+            Entry::Occupied(_) => Ok(false),
+            Entry::Vacant(e) => {
+                e.insert(user.clone());
+                Ok(true)
+            }
+        }
+    }
+
+    /// Removes `username` from `room`. Returns `true` if they were a member.
+    /// Drops the room from the registry entirely once its last member
+    /// leaves, so an abandoned room's history buffer doesn't linger forever.
+    pub fn part(&self, room: &RoomName, username: &Username) -> Result<bool, Error> {
+        let handle = self.handle_for(room)?;
+        let removed = {
+            let mut members = handle.members.try_write_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+            members.remove(&MemberKey::from_username(username)).is_some()
+        };
+        if removed {
+            self.remove_if_empty(room)?;
+        }
+        Ok(removed)
+    }
+
+    /// Evicts `room` from the registry if it currently has no members.
+    fn remove_if_empty(&self, room: &RoomName) -> Result<(), Error> {
+        let key = NormalizedRoomKey::from_name(room);
+        let mut rooms = self.rooms.try_write_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+        if let Some(handle) = rooms.get(&key) {
+            let is_empty = handle.members.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?.is_empty();
+            if is_empty {
+                rooms.remove(&key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Broadcasts `message` to every member of `room` except `exclude`.
+    pub async fn broadcast(
+        &self,
+        room: &RoomName,
+        message: &room::OneToMany,
+        exclude: Option<&Username>,
+    ) -> Result<usize, Error> {
+        let handle = self.handle_for(room)?;
+        broadcast_to(&handle, message, exclude).await
+    }
+
+    /// Records a successfully-sent chat message into the room's bounded
+    /// history ring buffer, evicting the oldest entry once `HISTORY_CAPACITY`
+    /// is exceeded.
+    pub fn record_history(&self, room: &RoomName, sender: Username, body: String) -> Result<(), Error> {
+        let handle = self.handle_for(room)?;
+        let mut history = handle.history.try_write_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+        if history.len() >= *HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(StoredMessage {
+            ts: SystemTime::now(),
+            sender,
+            body,
+        });
+        Ok(())
+    }
+
+    /// Returns up to `limit` of the most recent retained messages for `room`,
+    /// oldest first. `None` returns the full retained buffer.
+    pub fn history(&self, room: &RoomName, limit: Option<usize>) -> Result<Vec<StoredMessage>, Error> {
+        self.history_query(room, HistoryQuery::Latest(limit.unwrap_or(*HISTORY_CAPACITY)))
+    }
+
+    /// Returns the retained messages for `room` matching `query`, oldest
+    /// first. The snapshot is copied out from under the read lock before
+    /// being returned, so callers can stream it to a client without holding
+    /// the room's history lock for the duration of the write.
+    pub fn history_query(&self, room: &RoomName, query: HistoryQuery) -> Result<Vec<StoredMessage>, Error> {
+        let handle = self.handle_for(room)?;
+        let history = handle.history.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+        Ok(match query {
+            HistoryQuery::Latest(n) => {
+                let n = n.min(*HISTORY_CAPACITY);
+                history.iter().rev().take(n).rev().cloned().collect()
+            }
+            HistoryQuery::After(ts) => history.iter().filter(|m| m.ts > ts).cloned().collect(),
+        })
+    }
+}
+
+impl Default for RoomRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Broadcasts `message` to every member of a room handle except `exclude`,
+/// mirroring `UserRegistry::broadcast`'s bounded-concurrency fan-out.
+pub async fn broadcast_to(
+    handle: &RoomHandle,
+    message: &room::OneToMany,
+    exclude: Option<&Username>,
+) -> Result<usize, Error> {
+    let senders: Vec<_> = {
+        let guard = handle.members.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+        guard
+            .values()
+            .filter(|user| exclude != Some(&user.get_username()))
+            .map(|user| user.sender())
+            .collect()
+    };
+
+    let sent_count = stream::iter(senders)
+        .map(|tx| {
+            let msg = message.clone();
+            async move {
+                tokio::time::timeout(SEND_TIMEOUT, tx.send(msg))
+                    .await
+                    .is_ok_and(|r| r.is_ok())
+            }
+        })
+        .buffer_unordered(CONCURRENT_LIMIT)
+        .filter(|&success| async move { success })
+        .count()
+        .await;
+
+    Ok(sent_count)
+}
+
+#[cfg(test)]
+#[allow(clippy::unwrap_used)]
+mod tests {
+    use tokio::sync::mpsc;
+
+    use super::*;
+
+    fn test_user(name: &str) -> User {
+        let (tx, _rx) = mpsc::channel(256);
+        let username = Username::new(name).unwrap();
+        User::for_test(username, tx)
+    }
+
+    #[test]
+    fn test_room_name_rejects_empty() {
+        assert_eq!(RoomName::new("").unwrap_err(), Error::RoomEmpty);
+        assert_eq!(RoomName::new("   ").unwrap_err(), Error::RoomEmpty);
+    }
+
+    #[test]
+    fn test_join_creates_room_and_adds_member() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let user = test_user("alice");
+
+        assert!(registry.join(&room, &user).unwrap());
+        assert!(!registry.join(&room, &user).unwrap(), "re-join should be a no-op");
+    }
+
+    #[test]
+    fn test_join_is_case_insensitive() {
+        let registry = RoomRegistry::new();
+        let lower = RoomName::new("general").unwrap();
+        let upper = RoomName::new("GENERAL").unwrap();
+        let user = test_user("alice");
+
+        assert!(registry.join(&lower, &user).unwrap());
+        assert!(!registry.join(&upper, &user).unwrap());
+    }
+
+    #[test]
+    fn test_part_drops_room_once_empty() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let alice = Username::new("alice").unwrap();
+        let alice_user = test_user("alice");
+
+        registry.join(&room, &alice_user).unwrap();
+        registry.record_history(&room, alice.clone(), "hello".to_string()).unwrap();
+        assert!(registry.part(&room, &alice).unwrap());
+
+        // The room was evicted, so rejoining starts with a fresh, empty
+        // history buffer instead of replaying "hello".
+        let bob = test_user("bob");
+        registry.join(&room, &bob).unwrap();
+        assert!(registry.history(&room, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_part_removes_member() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let user = test_user("bob");
+
+        registry.join(&room, &user).unwrap();
+        assert!(registry.part(&room, &user.get_username()).unwrap());
+        assert!(!registry.part(&room, &user.get_username()).unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_excludes_sender() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+
+        let (tx1, mut rx1) = mpsc::channel(4);
+        let (tx2, mut rx2) = mpsc::channel(4);
+        let alice = User::for_test(Username::new("alice").unwrap(), tx1);
+        let bob = User::for_test(Username::new("bob").unwrap(), tx2);
+
+        registry.join(&room, &alice).unwrap();
+        registry.join(&room, &bob).unwrap();
+
+        let sent = registry
+            .broadcast(&room, &"hi".to_string(), Some(&alice.get_username()))
+            .await
+            .unwrap();
+
+        assert_eq!(sent, 1);
+        assert!(rx1.try_recv().is_err());
+        assert_eq!(rx2.try_recv().unwrap(), "hi");
+    }
+
+    #[test]
+    fn test_history_empty_room_returns_nothing() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+
+        assert!(registry.history(&room, None).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_history_returns_recorded_messages_in_order() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let alice = Username::new("alice").unwrap();
+
+        registry.record_history(&room, alice.clone(), "first".to_string()).unwrap();
+        registry.record_history(&room, alice.clone(), "second".to_string()).unwrap();
+
+        let history = registry.history(&room, None).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].body, "first");
+        assert_eq!(history[1].body, "second");
+    }
+
+    #[test]
+    fn test_history_limit_returns_most_recent() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let alice = Username::new("alice").unwrap();
+
+        for i in 0..5 {
+            registry.record_history(&room, alice.clone(), format!("msg{i}")).unwrap();
+        }
+
+        let history = registry.history(&room, Some(2)).unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].body, "msg3");
+        assert_eq!(history[1].body, "msg4");
+    }
+
+    #[test]
+    fn test_history_evicts_oldest_past_capacity() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let alice = Username::new("alice").unwrap();
+
+        for i in 0..(*HISTORY_CAPACITY + 10) {
+            registry
+                .record_history(&room, alice.clone(), format!("msg{i}"))
+                .unwrap();
+        }
+
+        let history = registry.history(&room, None).unwrap();
+        assert_eq!(history.len(), *HISTORY_CAPACITY);
+        assert_eq!(history[0].body, "msg10");
+    }
+
+    #[test]
+    fn test_history_query_after_returns_only_newer_entries() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let alice = Username::new("alice").unwrap();
+
+        registry.record_history(&room, alice.clone(), "before".to_string()).unwrap();
+        let cutoff = SystemTime::now();
+        std::thread::sleep(Duration::from_millis(5));
+        registry.record_history(&room, alice.clone(), "after".to_string()).unwrap();
+
+        let history = registry.history_query(&room, HistoryQuery::After(cutoff)).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].body, "after");
+    }
+
+    #[test]
+    fn test_history_query_after_now_is_empty() {
+        let registry = RoomRegistry::new();
+        let room = RoomName::new("general").unwrap();
+        let alice = Username::new("alice").unwrap();
+
+        registry.record_history(&room, alice, "msg".to_string()).unwrap();
+        let history = registry.history_query(&room, HistoryQuery::After(SystemTime::now())).unwrap();
+        assert!(history.is_empty());
+    }
+}
@@ -22,11 +22,28 @@ pub enum ServerMessage {
 
     Error { reason: String },
 
+    /// SASL-style auth failure, analogous to IRC's `ERR_SASLFAIL`. Distinct
+    /// from `Error` so a client can tell a credential rejection apart from
+    /// other failures and keep retrying from the `Unauthenticated` state.
+    AuthFailed { reason: String },
+
     UserJoined { username: String },
 
     UserLeft { username: String },
 
-    BroadcastMessage { text: String },
+    BroadcastMessage { room: String, text: String },
+
+    History { ts_millis: u64, sender: String, body: String },
+
+    HistoryEnd,
+
+    DirectMessage { text: String },
+
+    Users { names: Vec<String> },
+
+    /// Sent to every connected session as the server drains on shutdown,
+    /// right before its `cleanup` runs and the socket closes.
+    ServerShutdown { reason: String },
 }
 
 impl FromStr for ServerMessage {
@@ -51,12 +68,32 @@ impl FromStr for ServerMessage {
             consts::SERVER_ERR_CMD => Ok(Self::Error {
                 reason: rest.unwrap_or("unknown error").to_string(),
             }),
+            consts::SERVER_AUTH_FAILED_CMD => Ok(Self::AuthFailed {
+                reason: rest.unwrap_or("authentication failed").to_string(),
+            }),
             consts::SERVER_JOINED_CMD => Ok(Self::UserJoined {
                 username: rest.unwrap_or("").to_string(),
             }),
             consts::SERVER_LEFT_CMD => Ok(Self::UserLeft {
                 username: rest.unwrap_or("").to_string(),
             }),
+            consts::SERVER_DM_CMD => Ok(Self::DirectMessage {
+                text: rest.unwrap_or("").to_string(),
+            }),
+            consts::SERVER_USERS_CMD => Ok(Self::Users {
+                names: rest.unwrap_or("").split_whitespace().map(str::to_string).collect(),
+            }),
+            consts::SERVER_SHUTDOWN_CMD => Ok(Self::ServerShutdown {
+                reason: rest.unwrap_or("server is shutting down").to_string(),
+            }),
+            consts::SERVER_HISTORY_END_CMD => Ok(Self::HistoryEnd),
+            consts::SERVER_HISTORY_CMD => {
+                let mut fields = rest.unwrap_or("").splitn(3, ' ');
+                let ts_millis = fields.next().unwrap_or("").parse().unwrap_or(0);
+                let sender = fields.next().unwrap_or("").to_string();
+                let body = fields.next().unwrap_or("").to_string();
+                Ok(Self::History { ts_millis, sender, body })
+            }
             _ => Err(ParseError::UnknownCommand(command.to_string())),
         }
     }
@@ -67,18 +104,28 @@ impl Display for ServerMessage {
         match self {
             Self::Ok => write!(f, "{SERVER_OK_PREFIX}"),
             Self::Error { reason } => write!(f, "{}{reason}", consts::SERVER_ERR_PREFIX),
+            Self::AuthFailed { reason } => write!(f, "{}{reason}", consts::SERVER_AUTH_FAILED_PREFIX),
             Self::UserJoined { username } => write!(f, "{}{username}", consts::SERVER_JOINED_PREFIX),
             Self::UserLeft { username } => write!(f, "{}{username}", consts::SERVER_LEFT_PREFIX),
-            Self::BroadcastMessage { text } => write!(f, "{}{text}", consts::SERVER_BROADCAST_PREFIX),
+            Self::BroadcastMessage { room, text } => write!(f, "{}{room} {text}", consts::SERVER_BROADCAST_PREFIX),
+            Self::History { ts_millis, sender, body } => {
+                write!(f, "{}{ts_millis} {sender} {body}", consts::SERVER_HISTORY_PREFIX)
+            }
+            Self::HistoryEnd => write!(f, "{}", consts::SERVER_HISTORY_END_PREFIX),
+            Self::DirectMessage { text } => write!(f, "{}{text}", consts::SERVER_DM_PREFIX),
+            Self::Users { names } => write!(f, "{}{}", consts::SERVER_USERS_PREFIX, names.join(" ")),
+            Self::ServerShutdown { reason } => write!(f, "{}{reason}", consts::SERVER_SHUTDOWN_PREFIX),
         }
     }
 }
 
 #[cfg(test)]
+#[allow(clippy::unwrap_used)]
 mod tests {
     use common::consts;
 
     use super::*;
+    use crate::chat::{message::ChatMessage, user::{UserRegistry, Username}};
 
     #[test]
     fn test_ok_message_format_for_client() {
@@ -99,6 +146,26 @@ mod tests {
         assert_eq!(output, "ERR username taken");
     }
 
+    #[test]
+    fn test_auth_failed_message_format_for_client() {
+        let msg = ServerMessage::AuthFailed {
+            reason: "invalid password".to_string(),
+        };
+        let output = msg.to_string();
+
+        assert!(output.starts_with(consts::SERVER_AUTH_FAILED_CMD));
+        assert_eq!(output, "AUTH_FAILED invalid password");
+    }
+
+    #[test]
+    fn test_auth_failed_roundtrip() {
+        let original = ServerMessage::AuthFailed {
+            reason: "invalid password".to_string(),
+        };
+        let parsed: ServerMessage = original.to_string().parse().expect("should parse AUTH_FAILED");
+        assert_eq!(parsed, original);
+    }
+
     #[test]
     fn test_joined_message_format_for_client() {
         let msg = ServerMessage::UserJoined {
@@ -125,8 +192,11 @@ mod tests {
 
     #[test]
     fn test_broadcast_message_format_for_client() {
+        // `text` is shaped like `ChatMessage::serialize()`'s real
+        // `sender\x1fts\x1fcontent` frame, not the old colon-joined format.
         let msg = ServerMessage::BroadcastMessage {
-            text: "charlie:Hello everyone!".to_string(),
+            room: "general".to_string(),
+            text: "charlie\u{1f}2024-01-01T00:00:00Z\u{1f}Hello everyone!".to_string(),
         };
         let output = msg.to_string();
 
@@ -134,37 +204,163 @@ mod tests {
         assert!(rest.is_some(), "output should start with BROADCAST prefix");
         let rest = rest.unwrap_or("");
 
-        assert!(rest.contains(':'), "broadcast message should contain colon");
-        if let Some((from, text)) = rest.split_once(':') {
-            assert_eq!(from, "charlie");
-            assert_eq!(text, "Hello everyone!");
-        }
+        let (room, payload) = rest.split_once(' ').expect("room and payload should be space-separated");
+        assert_eq!(room, "general");
+
+        let mut fields = payload.splitn(3, '\u{1f}');
+        assert_eq!(fields.next(), Some("charlie"));
+        assert_eq!(fields.next(), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(fields.next(), Some("Hello everyone!"));
     }
 
     #[test]
     fn test_broadcast_empty_message_for_client() {
         let msg = ServerMessage::BroadcastMessage {
-            text: "dave:".to_string(),
+            room: "general".to_string(),
+            text: "dave\u{1f}2024-01-01T00:00:00Z\u{1f}".to_string(),
         };
         let output = msg.to_string();
         let rest = output.strip_prefix(consts::SERVER_BROADCAST_PREFIX);
         assert!(rest.is_some(), "output should start with BROADCAST prefix");
         let rest = rest.unwrap_or("");
-        assert!(rest.contains(':'), "broadcast message should contain colon");
-        if let Some((from, text)) = rest.split_once(':') {
-            assert_eq!(from, "dave");
-            assert_eq!(text, "");
-        }
+        let (room, payload) = rest.split_once(' ').expect("room and payload should be space-separated");
+        assert_eq!(room, "general");
+
+        let mut fields = payload.splitn(3, '\u{1f}');
+        assert_eq!(fields.next(), Some("dave"));
+        assert_eq!(fields.next(), Some("2024-01-01T00:00:00Z"));
+        assert_eq!(fields.next(), Some(""));
+    }
+
+    #[test]
+    fn test_broadcast_roundtrips_real_chat_message_frame() {
+        // Exercises the actual frame the runtime produces: a real
+        // `ChatMessage::serialize()` output wrapped in a `BroadcastMessage`,
+        // parsed back out the same way `client::parse_chat_frame` does
+        // (splitn(3, '\u{1f}') over the payload after the room).
+        let registry = UserRegistry::new();
+        let (tx, _rx) = tokio::sync::mpsc::channel(256);
+        let username = Username::new("charlie").unwrap();
+        let user = registry.register(&username, tx, false).unwrap();
+
+        let serialized = ChatMessage::new(&user, "Hello everyone!".to_string()).serialize();
+        let msg = ServerMessage::BroadcastMessage {
+            room: "general".to_string(),
+            text: serialized,
+        };
+        let output = msg.to_string();
+
+        let rest = output.strip_prefix(consts::SERVER_BROADCAST_PREFIX).unwrap();
+        let (room, payload) = rest.split_once(' ').unwrap();
+        assert_eq!(room, "general");
+
+        let mut fields = payload.splitn(3, '\u{1f}');
+        let from = fields.next().expect("sender field");
+        let ts = fields.next().expect("timestamp field");
+        let text = fields.next().expect("content field");
+
+        assert_eq!(from, "charlie");
+        assert_eq!(text, "Hello everyone!");
+        assert!(ts.parse::<jiff::Timestamp>().is_ok(), "timestamp field should be RFC3339");
     }
 
     #[test]
     fn test_broadcast_no_colon_fallback_for_client() {
         let msg = ServerMessage::BroadcastMessage {
+            room: "lobby".to_string(),
             text: "system notification".to_string(),
         };
         let output = msg.to_string();
 
         assert!(output.starts_with(consts::SERVER_BROADCAST_PREFIX));
-        assert_eq!(output, "BROADCAST system notification");
+        assert_eq!(output, "BROADCAST lobby system notification");
+    }
+
+    #[test]
+    fn test_direct_message_format_for_client() {
+        let msg = ServerMessage::DirectMessage {
+            text: "alice:psst".to_string(),
+        };
+        let output = msg.to_string();
+
+        assert_eq!(output, "DM alice:psst");
+    }
+
+    #[test]
+    fn test_direct_message_roundtrip() {
+        let original = ServerMessage::DirectMessage {
+            text: "alice:psst".to_string(),
+        };
+        let parsed: ServerMessage = original.to_string().parse().expect("should parse DM");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_users_message_format_for_client() {
+        let msg = ServerMessage::Users {
+            names: vec!["alice".to_string(), "bob".to_string(), "carol".to_string()],
+        };
+        assert_eq!(msg.to_string(), "USERS alice bob carol");
+    }
+
+    #[test]
+    fn test_users_message_roundtrip() {
+        let original = ServerMessage::Users {
+            names: vec!["alice".to_string(), "bob".to_string()],
+        };
+        let parsed: ServerMessage = original.to_string().parse().expect("should parse USERS");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_server_shutdown_message_format_for_client() {
+        let msg = ServerMessage::ServerShutdown {
+            reason: "server is shutting down".to_string(),
+        };
+        assert_eq!(msg.to_string(), "SERVER_SHUTDOWN server is shutting down");
+    }
+
+    #[test]
+    fn test_server_shutdown_roundtrip() {
+        let original = ServerMessage::ServerShutdown {
+            reason: "server is shutting down".to_string(),
+        };
+        let parsed: ServerMessage = original.to_string().parse().expect("should parse SERVER_SHUTDOWN");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_history_message_format_for_client() {
+        let msg = ServerMessage::History {
+            ts_millis: 1_700_000_000_000,
+            sender: "alice".to_string(),
+            body: "hello".to_string(),
+        };
+        let output = msg.to_string();
+
+        assert_eq!(output, "HISTORY 1700000000000 alice hello");
+    }
+
+    #[test]
+    fn test_history_end_message_format_for_client() {
+        let msg = ServerMessage::HistoryEnd;
+        assert_eq!(msg.to_string(), "HISTORY_END");
+    }
+
+    #[test]
+    fn test_history_roundtrip() {
+        let original = ServerMessage::History {
+            ts_millis: 42,
+            sender: "bob".to_string(),
+            body: "hi there".to_string(),
+        };
+        let parsed: ServerMessage = original.to_string().parse().expect("should parse HISTORY");
+        assert_eq!(parsed, original);
+    }
+
+    #[test]
+    fn test_history_end_roundtrip() {
+        let parsed: ServerMessage = ServerMessage::HistoryEnd.to_string().parse().expect("should parse HISTORY_END");
+        assert_eq!(parsed, ServerMessage::HistoryEnd);
     }
 }
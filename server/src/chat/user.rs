@@ -12,7 +12,7 @@ use thiserror::Error as this_error;
 use tokio::sync::mpsc::Sender;
 
 use super::string::{self as my_string, ValidationResult};
-use crate::chat::room;
+use crate::chat::{credentials, room};
 
 const SEND_TIMEOUT: Duration = Duration::from_millis(100);
 const LOCK_TIMEOUT: Duration = Duration::from_millis(50);
@@ -40,6 +40,12 @@ pub enum Error {
 
     #[error("registry lock timeout")]
     LockTimeout,
+
+    #[error("this username requires a successful AUTH before JOIN")]
+    AuthRequired,
+
+    #[error("no such user")]
+    UserOffline(String),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -82,6 +88,16 @@ impl User {
     pub fn get_username(&self) -> Username {
         self.username.clone()
     }
+
+    /// Clones this user's delivery sender, e.g. for room-scoped broadcast fan-out.
+    pub(crate) fn sender(&self) -> Sender<room::OneToMany> {
+        self.tx.clone()
+    }
+
+    #[cfg(test)]
+    pub(crate) fn for_test(username: Username, tx: Sender<room::OneToMany>) -> Self {
+        Self::new(username, tx)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -105,7 +121,22 @@ impl UserRegistry {
         }
     }
 
-    pub fn register(&self, username: &Username, tx: Sender<room::OneToMany>) -> Result<User, Error> {
+    /// Registers a new connection under `username`.
+    ///
+    /// `authenticated` must be `true` if the connection already completed a
+    /// successful `AUTH` for this username. Usernames with no entry in the
+    /// credential store don't require this and keep the open-registration
+    /// behavior.
+    pub fn register(
+        &self,
+        username: &Username,
+        tx: Sender<room::OneToMany>,
+        authenticated: bool,
+    ) -> Result<User, Error> {
+        if !authenticated && credentials::get_store().requires_auth(username) {
+            return Err(Error::AuthRequired);
+        }
+
         match self
             .users
             .try_write_for(LOCK_TIMEOUT)
@@ -129,6 +160,50 @@ impl UserRegistry {
             .is_some())
     }
 
+    /// Delivers `msg` to a single registered user, bypassing the broadcast
+    /// fan-out. Case-folds `target` the same way registration does, so `Msg`
+    /// commands can address a user regardless of how they capitalized JOIN.
+    /// Returns `Error::UserOffline` if `target` isn't currently registered or
+    /// the delivery itself times out.
+    pub async fn send_direct(&self, target: &Username, msg: &room::OneToMany) -> Result<(), Error> {
+        let tx = {
+            let guard = self.users.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+            match guard.get(&NormalizedKey::from_username(target)) {
+                Some(user) => user.tx.clone(),
+                None => return Err(Error::UserOffline(target.to_string())),
+            }
+        };
+
+        tokio::time::timeout(SEND_TIMEOUT, tx.send(msg.clone()))
+            .await
+            .is_ok_and(|r| r.is_ok())
+            .then_some(())
+            .ok_or_else(|| Error::UserOffline(target.to_string()))
+    }
+
+    /// Returns the usernames of all currently-registered connections,
+    /// sorted for deterministic `WHO` output.
+    pub fn list_usernames(&self) -> Result<Vec<Username>, Error> {
+        let guard = self.users.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+        let mut usernames: Vec<Username> = guard.values().map(User::get_username).collect();
+        usernames.sort_by(|a, b| a.to_string().cmp(&b.to_string()));
+        Ok(usernames)
+    }
+
+    /// Synchronous counterpart to [`Self::broadcast`], for callers running
+    /// outside a tokio task (the dispatcher's plain OS thread). Delivers via
+    /// each recipient's bounded channel with `try_send`, so a slow or full
+    /// recipient is skipped rather than blocking the dispatcher loop.
+    pub fn broadcast_blocking(&self, message: &room::OneToMany, exclude: Option<&Username>) -> Result<usize, Error> {
+        let guard = self.users.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
+        let sent_count = guard
+            .values()
+            .filter(|user| exclude != Some(&user.username))
+            .filter(|user| user.tx.try_send(message.clone()).is_ok())
+            .count();
+        Ok(sent_count)
+    }
+
     pub async fn broadcast(&self, message: &room::OneToMany, exclude: Option<&Username>) -> Result<usize, Error> {
         let senders: Vec<_> = {
             let guard = self.users.try_read_for(LOCK_TIMEOUT).ok_or(Error::LockTimeout)?;
@@ -208,7 +283,7 @@ mod tests {
         let (tx, _rx) = mpsc::channel(256);
         let username = Username::new("alice").unwrap();
 
-        let result = registry.register(&username, tx);
+        let result = registry.register(&username, tx, false);
         assert!(result.is_ok());
         assert_eq!(result.unwrap().get_username(), username);
     }
@@ -220,8 +295,8 @@ mod tests {
         let (tx2, _rx2) = mpsc::channel(256);
         let username = Username::new("bob").unwrap();
 
-        assert!(registry.register(&username, tx1).is_ok());
-        let err = registry.register(&username, tx2).unwrap_err();
+        assert!(registry.register(&username, tx1, false).is_ok());
+        let err = registry.register(&username, tx2, false).unwrap_err();
         assert_eq!(err, Error::UsernameTaken("bob".to_string()));
     }
 
@@ -234,8 +309,8 @@ mod tests {
         let alice_lower = Username::new("alice").unwrap();
         let alice_upper = Username::new("ALICE").unwrap();
 
-        assert!(registry.register(&alice_lower, tx1).is_ok());
-        let err = registry.register(&alice_upper, tx2).unwrap_err();
+        assert!(registry.register(&alice_lower, tx1, false).is_ok());
+        let err = registry.register(&alice_upper, tx2, false).unwrap_err();
         assert_eq!(err, Error::UsernameTaken("ALICE".to_string()));
     }
 
@@ -245,7 +320,7 @@ mod tests {
         let (tx, _rx) = mpsc::channel(256);
         let username = Username::new("charlie").unwrap();
 
-        let user = registry.register(&username, tx).unwrap();
+        let user = registry.register(&username, tx, false).unwrap();
         assert!(registry.unregister(&user).unwrap());
 
         assert!(!registry.unregister(&user).unwrap());
@@ -258,10 +333,81 @@ mod tests {
         let (tx2, _rx2) = mpsc::channel(256);
         let username = Username::new("dave").unwrap();
 
-        let user = registry.register(&username, tx1).unwrap();
+        let user = registry.register(&username, tx1, false).unwrap();
         assert!(registry.unregister(&user).unwrap());
 
-        assert!(registry.register(&username, tx2).is_ok());
+        assert!(registry.register(&username, tx2, false).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_send_direct_delivers_to_recipient() {
+        let registry = UserRegistry::new();
+        let (tx, mut rx) = mpsc::channel(4);
+        let bob = Username::new("bob").unwrap();
+        registry.register(&bob, tx, false).unwrap();
+
+        registry.send_direct(&bob, &"hi bob".to_string()).await.unwrap();
+        assert_eq!(rx.try_recv().unwrap(), "hi bob");
+    }
+
+    #[tokio::test]
+    async fn test_send_direct_is_case_insensitive() {
+        let registry = UserRegistry::new();
+        let (tx, mut rx) = mpsc::channel(4);
+        let bob = Username::new("Bob").unwrap();
+        registry.register(&bob, tx, false).unwrap();
+
+        registry
+            .send_direct(&Username::new("BOB").unwrap(), &"hi bob".to_string())
+            .await
+            .unwrap();
+        assert_eq!(rx.try_recv().unwrap(), "hi bob");
+    }
+
+    #[test]
+    fn test_broadcast_blocking_delivers_to_all_but_excluded() {
+        let registry = UserRegistry::new();
+        let (tx1, mut rx1) = mpsc::channel(4);
+        let (tx2, mut rx2) = mpsc::channel(4);
+        let alice = Username::new("alice").unwrap();
+        let bob = Username::new("bob").unwrap();
+        registry.register(&alice, tx1, false).unwrap();
+        registry.register(&bob, tx2, false).unwrap();
+
+        let sent = registry.broadcast_blocking(&"hi".to_string(), Some(&alice)).unwrap();
+        assert_eq!(sent, 1);
+        assert!(rx1.try_recv().is_err());
+        assert_eq!(rx2.try_recv().unwrap(), "hi");
+    }
+
+    #[tokio::test]
+    async fn test_send_direct_returns_error_when_offline() {
+        let registry = UserRegistry::new();
+        let offline = Username::new("ghost").unwrap();
+
+        let err = registry.send_direct(&offline, &"hello?".to_string()).await.unwrap_err();
+        assert!(matches!(err, Error::UserOffline(name) if name == "ghost"));
+    }
+
+    #[test]
+    fn test_list_usernames_sorted() {
+        let registry = UserRegistry::new();
+        let (tx1, _rx1) = mpsc::channel(256);
+        let (tx2, _rx2) = mpsc::channel(256);
+        let (tx3, _rx3) = mpsc::channel(256);
+
+        registry.register(&Username::new("carol").unwrap(), tx1, false).unwrap();
+        registry.register(&Username::new("alice").unwrap(), tx2, false).unwrap();
+        registry.register(&Username::new("bob").unwrap(), tx3, false).unwrap();
+
+        let names: Vec<String> = registry.list_usernames().unwrap().iter().map(ToString::to_string).collect();
+        assert_eq!(names, vec!["alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn test_list_usernames_empty() {
+        let registry = UserRegistry::new();
+        assert!(registry.list_usernames().unwrap().is_empty());
     }
 
     #[test]
@@ -2,8 +2,8 @@ mod chat;
 
 use std::{env, sync::Arc};
 
-use chat::{broker::get_broker, connection::handle_connection};
-use common::{security::MAX_CONNECTIONS, telemetry};
+use chat::{broker::get_broker, connection::handle_tcp_connection};
+use common::{config, security::MAX_CONNECTIONS, telemetry};
 use tokio::{net::TcpListener, sync::Semaphore};
 use tracing::{error, info, warn};
 
@@ -12,7 +12,8 @@ const DEFAULT_PORT: &str = "8080";
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let _guard = telemetry::init_logging().map_err(|e| format!("Failed to initialize logging: {e}"))?;
+    let telemetry_guard =
+        telemetry::init_logging().map_err(|e| format!("Failed to initialize logging: {e}"))?;
 
     let host = env::var("CHAT_HOST").unwrap_or_else(|_| DEFAULT_HOST.to_string());
     let port = env::var("CHAT_PORT").unwrap_or_else(|_| DEFAULT_PORT.to_string());
@@ -34,13 +35,38 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         info!("Shutdown signal received");
     };
 
+    // QUIC is an optional transport alongside TCP: it only starts once both
+    // a cert and key are configured, so deployments that don't need it pay
+    // no cost.
+    let quic_accept = async {
+        if let Some((cert_path, key_path)) = config::quic_cert_paths() {
+            let quic_addr = format!("{host}:{}", config::quic_port());
+            match quic_addr.parse() {
+                Ok(quic_addr) => {
+                    if let Err(e) =
+                        chat::quic::serve(quic_addr, cert_path.as_ref(), key_path.as_ref(), Arc::clone(&connection_semaphore))
+                            .await
+                    {
+                        error!("QUIC listener error: {e}");
+                    }
+                }
+                Err(e) => error!("Invalid QUIC address '{quic_addr}': {e}"),
+            }
+        } else {
+            std::future::pending::<()>().await;
+        }
+    };
+
     tokio::select! {
-        () = accept_connections(&listener, connection_semaphore) => {}
+        () = accept_connections(&listener, Arc::clone(&connection_semaphore)) => {}
+        () = quic_accept => {}
         () = shutdown => {
             info!("Shutting down server...");
+            get_broker().shutdown();
         }
     }
 
+    telemetry_guard.shutdown();
     info!("Server shutdown complete");
     Ok(())
 }
@@ -65,7 +91,7 @@ async fn accept_connections(listener: &TcpListener, semaphore: Arc<Semaphore>) {
             Ok((socket, addr)) => {
                 tokio::spawn(async move {
                     let _permit = permit;
-                    handle_connection(socket, addr).await;
+                    handle_tcp_connection(socket, addr).await;
                 });
             }
             Err(e) => {